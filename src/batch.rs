@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+use crate::format;
+
+/// Reads batch entries: a YAML list for .yaml/.yml files, otherwise one prompt per line.
+pub fn load(path: &Path) -> Result<Vec<Value>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read batch file {}", path.display()))?;
+
+    let is_yaml = matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("yaml" | "yml")
+    );
+    if !is_yaml {
+        return Ok(text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| json!({ "prompt": line }))
+            .collect());
+    }
+
+    let entries: Vec<Value> = serde_yaml::from_str(&text)
+        .with_context(|| format!("Failed to parse batch file {}", path.display()))?;
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| match entry {
+            Value::String(prompt) => Ok(json!({ "prompt": prompt })),
+            Value::Object(_) if entry["prompt"].is_string() => Ok(entry),
+            _ => anyhow::bail!("Batch entry {} needs a prompt", i + 1),
+        })
+        .collect()
+}
+
+pub fn default_output_dir(path: &Path) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("batch");
+    PathBuf::from(format!("{}-results", stem))
+}
+
+/// Writes one query's transcript and returns its path.
+pub fn write_transcript(
+    dir: &Path,
+    index: usize,
+    provider: &str,
+    prompt: &str,
+    composed: &str,
+    url: &str,
+) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{:03}-{}.md", index, slug(prompt)));
+
+    let fence = format::fence_for(composed);
+    let text = format!(
+        "# {}\n\n- Provider: {}\n- URL: <{}>\n\n{}text\n{}\n{}\n",
+        prompt, provider, url, fence, composed, fence
+    );
+    std::fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn slug(text: &str) -> String {
+    let slug: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .take(8)
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "query".to_string()
+    } else {
+        slug
+    }
+}
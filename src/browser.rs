@@ -0,0 +1,221 @@
+use std::cell::RefCell;
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::SearchError;
+use crate::wsl;
+
+#[derive(Debug, Clone, Default)]
+pub struct BrowserOptions {
+    /// Browser name such as "firefox", or a path to its executable
+    pub browser: Option<String>,
+    pub profile: Option<String>,
+    pub incognito: bool,
+    pub new_window: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Family {
+    Chromium,
+    Edge,
+    Firefox,
+    Safari,
+}
+
+struct Known {
+    name: &'static str,
+    family: Family,
+    linux: &'static str,
+    macos: &'static str,
+    windows: &'static str,
+}
+
+const KNOWN: &[Known] = &[
+    Known {
+        name: "chrome",
+        family: Family::Chromium,
+        linux: "google-chrome",
+        macos: "Google Chrome",
+        windows: "chrome",
+    },
+    Known {
+        name: "chromium",
+        family: Family::Chromium,
+        linux: "chromium",
+        macos: "Chromium",
+        windows: "chromium",
+    },
+    Known {
+        name: "brave",
+        family: Family::Chromium,
+        linux: "brave-browser",
+        macos: "Brave Browser",
+        windows: "brave",
+    },
+    Known {
+        name: "edge",
+        family: Family::Edge,
+        linux: "microsoft-edge",
+        macos: "Microsoft Edge",
+        windows: "msedge",
+    },
+    Known {
+        name: "firefox",
+        family: Family::Firefox,
+        linux: "firefox",
+        macos: "Firefox",
+        windows: "firefox",
+    },
+    Known {
+        name: "safari",
+        family: Family::Safari,
+        linux: "safari",
+        macos: "Safari",
+        windows: "safari",
+    },
+];
+
+pub trait Browser {
+    fn open(&self, url: &str) -> Result<(), SearchError>;
+}
+
+/// Launches the configured browser, or the system default.
+pub struct SystemBrowser<'a>(pub &'a BrowserOptions);
+
+impl Browser for SystemBrowser<'_> {
+    fn open(&self, url: &str) -> Result<(), SearchError> {
+        open(url, self.0)
+    }
+}
+
+/// Records opened URLs instead of launching anything.
+#[derive(Default)]
+pub struct RecordingBrowser {
+    opened: RefCell<Vec<String>>,
+}
+
+impl RecordingBrowser {
+    pub fn opened(&self) -> Vec<String> {
+        self.opened.borrow().clone()
+    }
+}
+
+impl Browser for RecordingBrowser {
+    fn open(&self, url: &str) -> Result<(), SearchError> {
+        self.opened.borrow_mut().push(url.to_string());
+        Ok(())
+    }
+}
+
+pub fn open(url: &str, options: &BrowserOptions) -> Result<(), SearchError> {
+    let Some(browser) = options.browser.as_deref() else {
+        if options.incognito || options.profile.is_some() {
+            return Err(failed(
+                "--incognito and --browser-profile need a browser, set with --browser",
+            ));
+        }
+        if wsl::detected() {
+            return wsl::open(url);
+        }
+        return open_default(url);
+    };
+
+    let known = KNOWN.iter().find(|k| k.name == browser.to_lowercase());
+    let family = match known {
+        Some(known) => known.family,
+        None => family_of(browser).ok_or_else(|| {
+            SearchError::BrowserFailed(format!(
+                "Unknown browser {:?}; pass a name or a path",
+                browser
+            ))
+        })?,
+    };
+
+    let mut flags = Vec::new();
+    if options.incognito {
+        flags.push(match family {
+            Family::Chromium => "--incognito".to_string(),
+            Family::Edge => "--inprivate".to_string(),
+            Family::Firefox => "--private-window".to_string(),
+            Family::Safari => return Err(failed("Safari can't be asked to open a private window")),
+        });
+    }
+    if options.new_window && family != Family::Safari {
+        flags.push("--new-window".to_string());
+    }
+    if let Some(profile) = &options.profile {
+        match family {
+            Family::Chromium | Family::Edge => {
+                flags.push(format!("--profile-directory={}", profile))
+            }
+            Family::Firefox => flags.extend(["-P".to_string(), profile.clone()]),
+            Family::Safari => {
+                return Err(failed(
+                    "Safari profiles can't be selected from the command line",
+                ))
+            }
+        }
+    }
+
+    let mut cmd = match known {
+        Some(known) if cfg!(target_os = "macos") => {
+            let mut cmd = Command::new("open");
+            cmd.args(["-na", known.macos]);
+            if !flags.is_empty() {
+                cmd.arg("--args").args(&flags);
+            }
+            cmd.arg(url);
+            cmd
+        }
+        _ => {
+            let program = match known {
+                Some(known) if cfg!(windows) => known.windows,
+                Some(known) => known.linux,
+                None => browser,
+            };
+            let mut cmd = Command::new(program);
+            cmd.args(&flags).arg(url);
+            cmd
+        }
+    };
+
+    cmd.spawn().map_err(|e| {
+        SearchError::BrowserFailed(format!("Failed to launch browser {}: {}", browser, e))
+    })?;
+    Ok(())
+}
+
+#[cfg(feature = "browser")]
+fn open_default(url: &str) -> Result<(), SearchError> {
+    webbrowser::open(url)
+        .map_err(|e| SearchError::BrowserFailed(format!("Failed to open browser: {}", e)))
+}
+
+#[cfg(not(feature = "browser"))]
+fn open_default(_url: &str) -> Result<(), SearchError> {
+    Err(failed(
+        "Built without the browser feature; pass --browser or use --print-url",
+    ))
+}
+
+fn failed(message: &str) -> SearchError {
+    SearchError::BrowserFailed(message.to_string())
+}
+
+fn family_of(path: &str) -> Option<Family> {
+    let name = Path::new(path).file_name()?.to_str()?.to_lowercase();
+    if name.contains("firefox") || name.contains("librewolf") {
+        Some(Family::Firefox)
+    } else if name.contains("edge") {
+        Some(Family::Edge)
+    } else if name.contains("safari") {
+        Some(Family::Safari)
+    } else if ["chrome", "chromium", "brave", "vivaldi", "opera"]
+        .iter()
+        .any(|n| name.contains(n))
+    {
+        Some(Family::Chromium)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,202 @@
+use std::cell::RefCell;
+
+use crate::error::SearchError;
+use crate::wsl;
+
+pub trait Clipboard {
+    /// The text on the clipboard, empty if it holds none.
+    fn get_text(&self) -> Result<String, SearchError>;
+    fn set_text(&self, text: &str) -> Result<(), SearchError>;
+
+    /// The HTML flavor of the contents, e.g. when copied from a browser.
+    fn get_html(&self) -> Result<Option<String>, SearchError> {
+        Ok(None)
+    }
+
+    fn has_image(&self) -> bool {
+        false
+    }
+}
+
+/// The desktop clipboard. On Linux, text set on it is handed to wl-copy, xclip, or xsel, which
+/// keep serving it after the process exits.
+pub struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&self) -> Result<String, SearchError> {
+        get_content()
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn set_text(&self, text: &str) -> Result<(), SearchError> {
+        if wsl::detected() {
+            return wsl::set_text(text);
+        }
+        set_content(text)
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    fn set_text(&self, text: &str) -> Result<(), SearchError> {
+        if wsl::detected() {
+            return wsl::set_text(text);
+        }
+        Err(SearchError::ClipboardUnavailable(
+            "built without the clipboard feature".to_string(),
+        ))
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn get_html(&self) -> Result<Option<String>, SearchError> {
+        if wsl::detected() {
+            return Ok(None);
+        }
+        match open()?.get().html() {
+            Ok(html) => Ok(Some(html)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(unavailable(e)),
+        }
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn has_image(&self) -> bool {
+        !wsl::detected() && open().is_ok_and(|mut c| c.get_image().is_ok())
+    }
+}
+
+/// A clipboard that only lives in memory, for tests and headless use.
+#[derive(Default)]
+pub struct MemoryClipboard {
+    text: RefCell<Option<String>>,
+    html: Option<String>,
+}
+
+impl MemoryClipboard {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: RefCell::new(Some(text.to_string())),
+            html: None,
+        }
+    }
+
+    pub fn with_html(text: &str, html: &str) -> Self {
+        Self {
+            text: RefCell::new(Some(text.to_string())),
+            html: Some(html.to_string()),
+        }
+    }
+
+    pub fn text(&self) -> Option<String> {
+        self.text.borrow().clone()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&self) -> Result<String, SearchError> {
+        Ok(self.text().unwrap_or_default())
+    }
+
+    fn set_text(&self, text: &str) -> Result<(), SearchError> {
+        *self.text.borrow_mut() = Some(text.to_string());
+        Ok(())
+    }
+
+    fn get_html(&self) -> Result<Option<String>, SearchError> {
+        Ok(self.html.clone())
+    }
+}
+
+// Copies from editors are usually styled spans that convert worse than their plain text.
+#[cfg(feature = "clipboard")]
+const STRUCTURE: &[&str] = &["<a ", "<pre", "<code", "<h1", "<h2", "<h3", "<li", "<table"];
+
+/// Converts copied HTML to Markdown, if it has links, code, headings, lists, or tables that
+/// the plain-text flavor would lose.
+#[cfg(feature = "clipboard")]
+pub fn to_markdown(html: &str) -> Option<String> {
+    let lower = html.to_lowercase();
+    if !STRUCTURE.iter().any(|tag| lower.contains(tag)) {
+        return None;
+    }
+    let markdown = htmd::convert(html).ok()?;
+    let markdown = markdown.trim();
+    (!markdown.is_empty()).then(|| markdown.to_string())
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn to_markdown(_html: &str) -> Option<String> {
+    None
+}
+
+#[cfg(feature = "clipboard")]
+fn open() -> Result<arboard::Clipboard, SearchError> {
+    arboard::Clipboard::new().map_err(unavailable)
+}
+
+#[cfg(feature = "clipboard")]
+fn unavailable(error: arboard::Error) -> SearchError {
+    SearchError::ClipboardUnavailable(error.to_string())
+}
+
+#[cfg(feature = "clipboard")]
+pub fn get_content() -> Result<String, SearchError> {
+    if wsl::detected() {
+        return wsl::get_text();
+    }
+    match open()?.get_text() {
+        Ok(text) => Ok(text),
+        Err(arboard::Error::ContentNotAvailable) => Ok(String::new()),
+        Err(e) => Err(unavailable(e)),
+    }
+}
+
+#[cfg(feature = "clipboard")]
+fn set_content(text: &str) -> Result<(), SearchError> {
+    // X11 and Wayland selections are served by their owner, so ours would vanish on exit.
+    #[cfg(target_os = "linux")]
+    if hand_off(text) {
+        return Ok(());
+    }
+    // Without one, a clipboard manager can still take the selection over.
+    open()?.set_text(text).map_err(unavailable)
+}
+
+/// Pipes the text to a tool that forks and owns the selection, returning whether one did.
+#[cfg(all(feature = "clipboard", target_os = "linux"))]
+fn hand_off(text: &str) -> bool {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut tools: Vec<&[&str]> = vec![
+        &["xclip", "-selection", "clipboard"],
+        &["xsel", "--clipboard", "--input"],
+    ];
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        tools.insert(0, &["wl-copy"]);
+    }
+    tools.into_iter().any(|tool| {
+        let Ok(mut child) = Command::new(tool[0])
+            .args(&tool[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            return false;
+        };
+        let written = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        child.wait().is_ok_and(|status| status.success()) && written
+    })
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn get_content() -> Result<String, SearchError> {
+    if wsl::detected() {
+        return wsl::get_text();
+    }
+    Err(SearchError::ClipboardUnavailable(
+        "built without the clipboard feature".to_string(),
+    ))
+}
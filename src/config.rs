@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::context;
+use crate::format::Order;
+use crate::provider::{Encoding, Style};
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Config {
+    pub providers: HashMap<String, ProviderConfig>,
+    /// Language server command per language, used by --definitions
+    pub lsp: HashMap<String, String>,
+    pub templates: Templates,
+    /// Whether the prompt goes before or after the context
+    pub order: Option<Order>,
+    /// Context sections by source name, e.g. ["diff", "files", "clipboard"]
+    pub source_order: Vec<String>,
+    /// Longest URL the browser handles reliably; longer prompts go through the clipboard
+    pub max_url_length: Option<usize>,
+    pub summarize: SummarizeConfig,
+    /// Seconds to wait for contextualize before killing it
+    pub contextualize_timeout_secs: Option<u64>,
+    /// Language to ask for answers in, e.g. "de" or "German"
+    pub lang: Option<String>,
+    /// Country whose search results to prefer, e.g. "de"
+    pub region: Option<String>,
+    /// Search engine locale, e.g. "de-DE"
+    pub locale: Option<String>,
+    /// Hours during which re-sending an identical prompt needs --force
+    pub duplicate_window_hours: Option<u64>,
+    pub history: HistoryConfig,
+    pub library: LibraryConfig,
+    pub serve: ServeConfig,
+    pub daemon: DaemonConfig,
+    pub webdriver: WebDriverConfig,
+    pub browser: BrowserConfig,
+    pub tabs: TabsConfig,
+    pub redact: RedactConfig,
+    pub paste: PasteConfig,
+    pub ground: GroundConfig,
+    pub confirm: ConfirmConfig,
+    pub hook: HookConfig,
+    pub dictate: DictateConfig,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct DictateConfig {
+    /// Command that records and transcribes by itself, printing the text
+    pub command: Option<String>,
+    /// Recorder writing 16 kHz mono WAV to `{file}` until interrupted
+    pub record: Option<String>,
+    /// Transcriber reading `{file}` and printing the text, e.g. whisper.cpp with `-m <model>`
+    pub transcribe: Option<String>,
+}
+
+impl DictateConfig {
+    pub fn record(&self) -> &str {
+        self.record
+            .as_deref()
+            .unwrap_or("rec -q -c 1 -r 16000 -b 16 {file}")
+    }
+
+    pub fn transcribe(&self) -> &str {
+        self.transcribe
+            .as_deref()
+            .unwrap_or("whisper-cli --no-timestamps --no-prints -f {file}")
+    }
+}
+
+/// Called with a JSON summary of each query a batch completes.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct HookConfig {
+    /// URL the summary is POSTed to
+    pub url: Option<String>,
+    /// Command that gets the summary on stdin
+    pub command: Option<String>,
+    /// Seconds to wait for the webhook
+    pub timeout_secs: Option<u64>,
+}
+
+impl HookConfig {
+    pub fn timeout_secs(&self) -> u64 {
+        self.timeout_secs.unwrap_or(10)
+    }
+}
+
+/// Prompts over either limit are summarized and need confirming before they're sent.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ConfirmConfig {
+    pub bytes: Option<usize>,
+    /// Estimated from the size, at about four bytes per token
+    pub tokens: Option<usize>,
+}
+
+impl ConfirmConfig {
+    /// Whether a prompt of this size is over either limit.
+    pub fn exceeded(&self, bytes: usize) -> bool {
+        self.bytes.is_some_and(|limit| bytes > limit)
+            || self
+                .tokens
+                .is_some_and(|limit| context::estimate_tokens(bytes) > limit)
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct GroundConfig {
+    /// Search API URL with a `{query}` placeholder, e.g. a SearXNG instance with format=json
+    pub url: Option<String>,
+    /// Environment variable holding the search API key
+    pub api_key_env: Option<String>,
+    /// Header the API key is sent in
+    pub api_key_header: Option<String>,
+    /// Number of results to fetch and include
+    pub results: Option<usize>,
+    /// Most bytes of text to keep from each result
+    pub max_bytes: Option<usize>,
+    /// Seconds to wait for the search API and each page
+    pub timeout_secs: Option<u64>,
+}
+
+impl GroundConfig {
+    pub fn api_key_header(&self) -> &str {
+        self.api_key_header
+            .as_deref()
+            .unwrap_or("X-Subscription-Token")
+    }
+
+    pub fn results(&self) -> usize {
+        self.results.unwrap_or(3)
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes.unwrap_or(4_000)
+    }
+
+    pub fn timeout_secs(&self) -> u64 {
+        self.timeout_secs.unwrap_or(10)
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct PasteConfig {
+    /// Endpoint that takes a prompt too long for a URL as a POST body and returns a link
+    pub url: Option<String>,
+    /// Upload as a secret GitHub gist instead of to `url`
+    pub gist: bool,
+    /// Environment variable with a bearer token for the upload
+    pub token_env: Option<String>,
+    /// JSON field of the response that holds the link; otherwise the whole body is the link
+    pub link_field: Option<String>,
+    /// Upload oversized prompts without passing --paste-overflow
+    pub auto: bool,
+}
+
+impl PasteConfig {
+    pub fn token_env(&self) -> &str {
+        self.token_env.as_deref().unwrap_or(if self.gist {
+            "GITHUB_TOKEN"
+        } else {
+            "SEARCH_PASTE_TOKEN"
+        })
+    }
+
+    pub fn link_field(&self) -> Option<&str> {
+        match (&self.link_field, self.gist) {
+            (Some(field), _) => Some(field),
+            (None, true) => Some("html_url"),
+            (None, false) => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct RedactConfig {
+    /// Show what redaction changed and ask before sending
+    pub confirm: bool,
+    /// Extra secret patterns by name; a `secret` group limits what gets replaced
+    pub rules: HashMap<String, String>,
+    /// Named patterns whose matches are left alone, e.g. keys in test fixtures
+    pub allow: HashMap<String, String>,
+    /// Rule names to turn off, built-in or configured
+    pub disable: Vec<String>,
+    /// Extra rules, allowlist entries, and disabled rules for directories and everything under them
+    pub projects: HashMap<PathBuf, RedactConfig>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct TabsConfig {
+    /// Milliseconds to wait between tabs opened by batch mode
+    pub delay_ms: Option<u64>,
+    /// Tabs batch mode opens before asking to continue
+    pub max_tabs: Option<usize>,
+    /// Open batch tabs together in a new window, when a browser is configured
+    pub new_window: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct BrowserConfig {
+    /// Browser name or executable path, instead of the system default
+    pub name: Option<String>,
+    /// Browser profile to open provider URLs in
+    pub profile: Option<String>,
+    /// Always open a private window
+    pub incognito: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct WebDriverConfig {
+    /// Address of a running chromedriver or geckodriver
+    pub url: Option<String>,
+    /// Browser to request from the driver, "chrome" or "firefox"
+    pub browser: Option<String>,
+}
+
+impl WebDriverConfig {
+    pub fn url(&self) -> &str {
+        self.url.as_deref().unwrap_or("http://localhost:9515")
+    }
+
+    pub fn browser(&self) -> &str {
+        self.browser.as_deref().unwrap_or("chrome")
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Global hotkey for `search daemon`, e.g. "ctrl+alt+KeyS"
+    pub hotkey: Option<String>,
+    /// Command that asks for a prompt and prints it, e.g. "zenity --entry"
+    pub prompt_command: Option<String>,
+    /// Prefix that makes copied text a query, e.g. "??"
+    pub trigger: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ServeConfig {
+    /// Browser origins allowed to call `search serve`, e.g. for bookmarklets
+    pub allowed_origins: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct LibraryConfig {
+    /// Git repository of shared templates and context bundles
+    pub repo: Option<String>,
+    /// Branch to check out when cloning the library
+    pub branch: Option<String>,
+    /// Where the library lives locally, instead of the data directory
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Encrypt new history entries at rest
+    pub encrypt: bool,
+    /// Key used for history encryption, created on first use
+    pub key_file: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct SummarizeConfig {
+    /// Ollama-compatible endpoint used by --summarize
+    pub url: Option<String>,
+    /// Local model used by --summarize
+    pub model: Option<String>,
+}
+
+impl SummarizeConfig {
+    pub fn url(&self) -> &str {
+        self.url.as_deref().unwrap_or("http://localhost:11434")
+    }
+
+    pub fn model(&self) -> &str {
+        self.model.as_deref().unwrap_or("llama3.2")
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct Templates {
+    /// Wrapper for pasted context, e.g. "<paste source=\"{source}\">\n{content}\n</paste>"
+    pub paste: Option<String>,
+    /// Wrapper for each context file, e.g. "{path}\n```{lang}\n{content}\n```"
+    pub file: Option<String>,
+}
+
+impl Templates {
+    pub fn or(&self, fallback: &Templates) -> Templates {
+        Templates {
+            paste: self.paste.clone().or_else(|| fallback.paste.clone()),
+            file: self.file.clone().or_else(|| fallback.file.clone()),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ProviderConfig {
+    /// URL template with a `{query}` placeholder, for custom providers
+    pub url: Option<String>,
+    /// Blank new-chat URL, opened when the prompt is too long for a URL
+    pub home: Option<String>,
+    /// Longest URL the provider accepts without truncating the query
+    pub max_url_length: Option<usize>,
+    /// Percent-encoding profile for the query
+    pub encoding: Option<Encoding>,
+    /// Extra characters to percent-encode, for sites that mishandle them
+    pub encode_chars: Option<String>,
+    /// Context formatting style
+    pub style: Option<Style>,
+    /// Context wrapper templates, overriding the global ones
+    pub templates: Templates,
+    /// Prompt placement, overriding the global one
+    pub order: Option<Order>,
+    /// Context section order, overriding the global one
+    pub source_order: Option<Vec<String>>,
+    /// Text placed before the composed prompt
+    pub prefix: Option<String>,
+    /// Text placed after the composed prompt
+    pub suffix: Option<String>,
+    /// CSS selector of the chat input, for --webdriver
+    pub input_selector: Option<String>,
+    /// macOS desktop app name, for --desktop
+    pub app: Option<String>,
+    /// Query parameter that sets the provider's interface language, e.g. "hl"
+    pub lang_param: Option<String>,
+    /// Query parameter that sets the country of search results, e.g. "gl"
+    pub region_param: Option<String>,
+    /// Conversation URL template for --chat, with an `{id}` and optionally a `{query}` placeholder
+    pub chat_url: Option<String>,
+}
+
+impl Config {
+    pub fn load() -> Result<Self> {
+        let Some(path) = config_path() else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&text)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+
+    pub fn provider(&self, name: &str) -> Option<&ProviderConfig> {
+        self.providers.get(name)
+    }
+
+    pub fn templates(&self, provider: &str) -> Templates {
+        match self.provider(provider) {
+            Some(provider) => provider.templates.or(&self.templates),
+            None => self.templates.clone(),
+        }
+    }
+}
+
+impl Config {
+    pub fn order(&self, provider: &str) -> Option<Order> {
+        self.provider(provider).and_then(|p| p.order).or(self.order)
+    }
+
+    pub fn source_order(&self, provider: &str) -> Vec<String> {
+        self.provider(provider)
+            .and_then(|p| p.source_order.clone())
+            .unwrap_or_else(|| self.source_order.clone())
+    }
+}
+
+impl ProviderConfig {
+    pub fn wrap(&self, prompt: &str) -> String {
+        [self.prefix.as_deref(), Some(prompt), self.suffix.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Appends a TOML section to the config file, creating the file if needed.
+pub fn append(section: &str) -> Result<PathBuf> {
+    let path = config_path().context("Could not determine the config file location")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut text = std::fs::read_to_string(&path).unwrap_or_default();
+    if !text.is_empty() {
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push('\n');
+    }
+    text.push_str(section);
+    std::fs::write(&path, text)
+        .with_context(|| format!("Failed to write config file {}", path.display()))?;
+    Ok(path)
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("SEARCH_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("search").join("config.toml"))
+}
+
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("SEARCH_DATA_DIR") {
+        return Some(PathBuf::from(path));
+    }
+
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })?;
+    Some(base.join("search"))
+}
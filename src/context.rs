@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use rayon::prelude::*;
+
+use crate::output;
+
+#[derive(Debug)]
+pub struct ContextFile {
+    pub path: PathBuf,
+    pub content: String,
+    pub lang: Option<&'static str>,
+    pub size: usize,
+    pub truncated: bool,
+    pub note: Option<String>,
+}
+
+pub fn collect(paths: &[String], max_file_bytes: usize) -> Result<Vec<ContextFile>> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let entries = walk(paths)?;
+
+    // Reads are independent and slow on network filesystems, so do them in parallel.
+    let files = entries
+        .par_iter()
+        .map(|path| read_file(path, &cwd, max_file_bytes))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(files.into_iter().flatten().collect())
+}
+
+/// The files under the given paths, minus ignored ones, in a stable order.
+pub fn walk(paths: &[String]) -> Result<Vec<PathBuf>> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let ignore = search_ignore(&cwd)?;
+    let mut entries = Vec::new();
+
+    for path in paths {
+        let root = Path::new(path);
+        if !root.exists() {
+            anyhow::bail!("Context path does not exist: {}", path);
+        }
+
+        for entry in WalkBuilder::new(root)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build()
+        {
+            let entry = entry.with_context(|| format!("Failed to walk {}", path))?;
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let explicit = entry.depth() == 0;
+            let path = entry.into_path();
+            if let Some(ignore) = &ignore {
+                let absolute = cwd.join(&path);
+                // The matcher only handles paths inside the workspace.
+                if absolute.starts_with(ignore.path())
+                    && ignore
+                        .matched_path_or_any_parents(&absolute, false)
+                        .is_ignore()
+                {
+                    if explicit {
+                        output::warn(format!(
+                            "Skipping {}, excluded by .searchignore",
+                            path.display()
+                        ));
+                    }
+                    continue;
+                }
+            }
+            entries.push(path);
+        }
+    }
+    Ok(entries)
+}
+
+/// The `.searchignore` of the workspace, looked up from `dir` to the repository root.
+fn search_ignore(dir: &Path) -> Result<Option<Gitignore>> {
+    for root in dir.ancestors() {
+        let file = root.join(".searchignore");
+        if file.is_file() {
+            let mut builder = GitignoreBuilder::new(root);
+            if let Some(e) = builder.add(&file) {
+                return Err(e).with_context(|| format!("Failed to read {}", file.display()));
+            }
+            return builder
+                .build()
+                .map(Some)
+                .with_context(|| format!("Failed to parse {}", file.display()));
+        }
+        if root.join(".git").exists() {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+fn read_file(path: &Path, cwd: &Path, max_file_bytes: usize) -> Result<Option<ContextFile>> {
+    let file = File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let size = file.metadata().map_or(0, |m| m.len() as usize);
+
+    // Only read what can be kept, so an accidentally included huge file stays cheap.
+    let mut bytes = Vec::with_capacity(size.min(max_file_bytes) + 1);
+    file.take(max_file_bytes as u64 + 1)
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if bytes.iter().take(8192).any(|&b| b == 0) {
+        return Ok(None);
+    }
+    let size = size.max(bytes.len());
+    let truncated = size > max_file_bytes;
+
+    let mut content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        // The read limit can split a multi-byte character at the end.
+        Err(e) if truncated && e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes).expect("prefix is valid UTF-8")
+        }
+        Err(_) => return Ok(None),
+    };
+    if truncated {
+        truncate(&mut content, max_file_bytes);
+    }
+
+    let lang = detect_language(path, &content);
+    Ok(Some(ContextFile {
+        path: relative_path(path, cwd),
+        content,
+        lang,
+        size,
+        truncated,
+        note: None,
+    }))
+}
+
+pub fn read_path(path: &Path, max_file_bytes: usize) -> Result<ContextFile> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    read_file(path, &cwd, max_file_bytes)?
+        .with_context(|| format!("{} is not a text file", path.display()))
+}
+
+fn truncate(content: &mut String, max_bytes: usize) {
+    let mut end = max_bytes;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    if let Some(newline) = content[..end].rfind('\n') {
+        end = newline + 1;
+    }
+    content.truncate(end);
+}
+
+pub fn relative_path(path: &Path, cwd: &Path) -> PathBuf {
+    let path = path.strip_prefix("./").unwrap_or(path);
+    path.strip_prefix(cwd).unwrap_or(path).to_path_buf()
+}
+
+pub fn apply_budget(files: &mut Vec<ContextFile>, budget: usize) -> Vec<ContextFile> {
+    let mut used = 0;
+    let mut keep = files.len();
+
+    for (i, file) in files.iter_mut().enumerate() {
+        let remaining = budget.saturating_sub(used);
+        if remaining == 0 {
+            keep = i;
+            break;
+        }
+        if file.content.len() > remaining {
+            truncate(&mut file.content, remaining);
+            file.truncated = true;
+        }
+        used += file.content.len();
+    }
+
+    files.split_off(keep)
+}
+
+/// Drops files that repeat an earlier one, either the same file reached through two paths or a
+/// copy whose content differs only in whitespace. Returns the dropped paths with the kept ones.
+pub fn dedup(files: &mut Vec<ContextFile>) -> Vec<(PathBuf, PathBuf)> {
+    let mut seen_paths: HashMap<PathBuf, usize> = HashMap::new();
+    let mut seen_content: HashMap<u64, usize> = HashMap::new();
+    let mut duplicates = Vec::new();
+    let mut kept: Vec<ContextFile> = Vec::with_capacity(files.len());
+
+    for file in files.drain(..) {
+        let real = std::fs::canonicalize(&file.path).unwrap_or_else(|_| file.path.clone());
+        if let Some(&i) = seen_paths.get(&real) {
+            duplicates.push((file.path, kept[i].path.clone()));
+            continue;
+        }
+
+        let normalized = normalize(&file.content);
+        let hash = (!normalized.is_empty()).then(|| {
+            let mut hasher = DefaultHasher::new();
+            normalized.hash(&mut hasher);
+            hasher.finish()
+        });
+        if let Some(&i) = hash.and_then(|hash| seen_content.get(&hash)) {
+            let original = &mut kept[i];
+            let also = format!("also at {}", file.path.display());
+            original.note = Some(match original.note.take() {
+                Some(note) => format!("{}; {}", note, also),
+                None => also,
+            });
+            duplicates.push((file.path, original.path.clone()));
+            continue;
+        }
+
+        seen_paths.insert(real, kept.len());
+        if let Some(hash) = hash {
+            seen_content.insert(hash, kept.len());
+        }
+        kept.push(file);
+    }
+
+    *files = kept;
+    duplicates
+}
+
+fn normalize(content: &str) -> String {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// A rough count for sizing prompts; real tokenizers average about four bytes per token.
+pub fn estimate_tokens(bytes: usize) -> usize {
+    bytes.div_ceil(4)
+}
+
+pub fn detect_language(path: &Path, content: &str) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?;
+    let by_name = match name {
+        "Makefile" | "GNUmakefile" => Some("makefile"),
+        "Dockerfile" | "Containerfile" => Some("dockerfile"),
+        "CMakeLists.txt" => Some("cmake"),
+        "Cargo.lock" | "Pipfile" => Some("toml"),
+        _ => None,
+    };
+    if by_name.is_some() {
+        return by_name;
+    }
+
+    let by_ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| match ext.to_ascii_lowercase().as_str() {
+            "rs" => Some("rust"),
+            "py" | "pyi" => Some("python"),
+            "js" | "mjs" | "cjs" => Some("javascript"),
+            "jsx" => Some("jsx"),
+            "ts" | "mts" | "cts" => Some("typescript"),
+            "tsx" => Some("tsx"),
+            "go" => Some("go"),
+            "c" | "h" => Some("c"),
+            "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" => Some("cpp"),
+            "cs" => Some("csharp"),
+            "java" => Some("java"),
+            "kt" | "kts" => Some("kotlin"),
+            "swift" => Some("swift"),
+            "rb" => Some("ruby"),
+            "php" => Some("php"),
+            "lua" => Some("lua"),
+            "sh" | "bash" => Some("bash"),
+            "zsh" => Some("zsh"),
+            "fish" => Some("fish"),
+            "ps1" => Some("powershell"),
+            "sql" => Some("sql"),
+            "html" | "htm" => Some("html"),
+            "css" => Some("css"),
+            "scss" => Some("scss"),
+            "json" => Some("json"),
+            "yaml" | "yml" => Some("yaml"),
+            "toml" => Some("toml"),
+            "xml" => Some("xml"),
+            "md" | "markdown" => Some("markdown"),
+            "nix" => Some("nix"),
+            "hs" => Some("haskell"),
+            "ex" | "exs" => Some("elixir"),
+            "erl" => Some("erlang"),
+            "clj" | "cljs" => Some("clojure"),
+            "scala" => Some("scala"),
+            "dart" => Some("dart"),
+            "zig" => Some("zig"),
+            "vue" => Some("vue"),
+            "svelte" => Some("svelte"),
+            _ => None,
+        });
+    if by_ext.is_some() {
+        return by_ext;
+    }
+
+    detect_from_content(content)
+}
+
+fn detect_from_content(content: &str) -> Option<&'static str> {
+    let first = content.lines().next()?.trim();
+
+    if let Some(shebang) = first.strip_prefix("#!") {
+        let interpreter = shebang
+            .split_whitespace()
+            .find(|part| !part.ends_with("/env") && !part.starts_with('-'))?;
+        let interpreter = interpreter.rsplit('/').next()?;
+        return match interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+            "python" => Some("python"),
+            "bash" | "sh" | "dash" => Some("bash"),
+            "zsh" => Some("zsh"),
+            "fish" => Some("fish"),
+            "node" | "deno" | "bun" => Some("javascript"),
+            "ruby" => Some("ruby"),
+            "perl" => Some("perl"),
+            "php" => Some("php"),
+            "lua" => Some("lua"),
+            _ => None,
+        };
+    }
+
+    if first.starts_with("<?php") {
+        Some("php")
+    } else if first.starts_with("<?xml") {
+        Some("xml")
+    } else if first.to_ascii_lowercase().starts_with("<!doctype html") {
+        Some("html")
+    } else {
+        None
+    }
+}
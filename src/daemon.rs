@@ -0,0 +1,163 @@
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use arboard::Clipboard;
+
+use crate::config::DaemonConfig;
+use crate::output;
+
+const DEFAULT_HOTKEY: &str = "ctrl+alt+KeyS";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Calls `dispatch` with the selection and a prompt whenever the global hotkey is pressed or,
+/// with a trigger prefix, whenever copied text starts with it.
+pub fn run(
+    config: &DaemonConfig,
+    mut dispatch: impl FnMut(String, String) -> Result<()>,
+) -> Result<()> {
+    let hotkey = Hotkey::register(config)?;
+    let trigger = config.trigger.as_deref().filter(|t| !t.is_empty());
+    let mut clipboard = match trigger {
+        Some(trigger) => {
+            output::note(format!(
+                "Watching the clipboard for text starting with {:?}",
+                trigger
+            ));
+            Some(Clipboard::new().context("Failed to initialize clipboard")?)
+        }
+        None => None,
+    };
+    let mut last_copied = clipboard.as_mut().and_then(|c| c.get_text().ok());
+
+    loop {
+        if hotkey.pressed(POLL_INTERVAL)? {
+            let selection = selection().unwrap_or_default();
+            if let Some(prompt) = ask_prompt(config)? {
+                if let Err(e) = dispatch(selection, prompt) {
+                    output::error(format!("{:#}", e));
+                }
+            }
+        }
+
+        if let (Some(trigger), Some(clipboard)) = (trigger, clipboard.as_mut()) {
+            let Ok(text) = clipboard.get_text() else {
+                continue;
+            };
+            if last_copied.as_ref() == Some(&text) {
+                continue;
+            }
+            last_copied = Some(text.clone());
+            if let Some(prompt) = text.trim_start().strip_prefix(trigger) {
+                let prompt = prompt.trim();
+                if !prompt.is_empty() {
+                    if let Err(e) = dispatch(String::new(), prompt.to_string()) {
+                        output::error(format!("{:#}", e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct Hotkey {
+    id: u32,
+    _manager: global_hotkey::GlobalHotKeyManager,
+}
+
+#[cfg(target_os = "linux")]
+impl Hotkey {
+    fn register(config: &DaemonConfig) -> Result<Self> {
+        use global_hotkey::hotkey::HotKey;
+        use global_hotkey::GlobalHotKeyManager;
+
+        let spec = config.hotkey.as_deref().unwrap_or(DEFAULT_HOTKEY);
+        let hotkey: HotKey = spec
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid hotkey {:?}: {}", spec, e))?;
+        let manager = GlobalHotKeyManager::new()
+            .map_err(|e| anyhow::anyhow!("Failed to start the hotkey listener: {}", e))?;
+        manager
+            .register(hotkey)
+            .map_err(|e| anyhow::anyhow!("Failed to register hotkey {}: {}", spec, e))?;
+        output::note(format!("Waiting for {}", spec));
+
+        Ok(Self {
+            id: hotkey.id(),
+            _manager: manager,
+        })
+    }
+
+    fn pressed(&self, timeout: Duration) -> Result<bool> {
+        use global_hotkey::{GlobalHotKeyEvent, HotKeyState};
+
+        match GlobalHotKeyEvent::receiver().recv_timeout(timeout) {
+            Ok(event) => Ok(event.id() == self.id && event.state() == HotKeyState::Pressed),
+            Err(e) if e.is_timeout() => Ok(false),
+            Err(_) => anyhow::bail!("Hotkey listener stopped"),
+        }
+    }
+}
+
+// Global hotkeys need a host event loop outside Linux, so only the clipboard trigger works there.
+#[cfg(not(target_os = "linux"))]
+struct Hotkey;
+
+#[cfg(not(target_os = "linux"))]
+impl Hotkey {
+    fn register(config: &DaemonConfig) -> Result<Self> {
+        if config.trigger.is_none() {
+            anyhow::bail!("Global hotkeys are only supported on Linux (X11); set a daemon trigger to watch the clipboard instead");
+        }
+        Ok(Self)
+    }
+
+    fn pressed(&self, timeout: Duration) -> Result<bool> {
+        std::thread::sleep(timeout);
+        Ok(false)
+    }
+}
+
+/// The primary selection, falling back to the clipboard.
+fn selection() -> Result<String> {
+    let mut clipboard = Clipboard::new().context("Failed to initialize clipboard")?;
+    #[cfg(target_os = "linux")]
+    {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+
+        let primary = clipboard
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text();
+        if let Ok(text) = primary {
+            if !text.trim().is_empty() {
+                return Ok(text);
+            }
+        }
+    }
+    clipboard
+        .get_text()
+        .context("Failed to get clipboard content")
+}
+
+/// Runs the prompt dialog, returning `None` if it was cancelled.
+fn ask_prompt(config: &DaemonConfig) -> Result<Option<String>> {
+    let command = config
+        .prompt_command
+        .as_deref()
+        .unwrap_or("zenity --entry --title=search --text=Prompt:");
+    let mut words = command.split_whitespace();
+    let program = words.next().context("daemon prompt_command is empty")?;
+
+    let output = Command::new(program)
+        .args(words)
+        .output()
+        .with_context(|| format!("Failed to run prompt command {:?}", command))?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let prompt = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((!prompt.is_empty()).then_some(prompt))
+}
@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+pub fn local_dependencies(file: &Path, content: &str) -> Vec<PathBuf> {
+    let ext = file.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let mut found = match ext {
+        "rs" => rust_dependencies(file, content),
+        "py" | "pyi" => python_dependencies(file, content),
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => {
+            js_dependencies(file, content)
+        }
+        _ => Vec::new(),
+    };
+
+    let mut seen = Vec::new();
+    found.retain(|path| {
+        let keep = path != file && !seen.contains(path);
+        if keep {
+            seen.push(path.clone());
+        }
+        keep
+    });
+    found
+}
+
+fn rust_dependencies(file: &Path, content: &str) -> Vec<PathBuf> {
+    let content = strip_line_comments(content, "//");
+    let mod_dir = rust_module_dir(file);
+    let mut found = Vec::new();
+
+    let mod_re = Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+(\w+)\s*;").unwrap();
+    for cap in mod_re.captures_iter(&content) {
+        if let Some(path) = rust_module_file(&mod_dir, &cap[1]) {
+            found.push(path);
+        }
+    }
+
+    let use_re = Regex::new(r"(?s)\buse\s+([^;]+);").unwrap();
+    for cap in use_re.captures_iter(&content) {
+        for path in expand_use_tree(&cap[1].split_whitespace().collect::<String>()) {
+            let segments: Vec<&str> = path.split("::").filter(|s| !s.is_empty()).collect();
+            let Some((first, rest)) = segments.split_first() else {
+                continue;
+            };
+            let (base, rest) = match *first {
+                "crate" => match rust_crate_root(file) {
+                    Some(root) => (root, rest),
+                    None => continue,
+                },
+                "self" => (mod_dir.clone(), rest),
+                "super" => {
+                    let mut base = mod_dir.clone();
+                    let mut rest = rest;
+                    base.pop();
+                    while let Some(("super", tail)) = rest.split_first().map(|(h, t)| (*h, t)) {
+                        base.pop();
+                        rest = tail;
+                    }
+                    (base, rest)
+                }
+                "std" | "core" | "alloc" => continue,
+                _ => (mod_dir.clone(), segments.as_slice()),
+            };
+            if let Some(path) = resolve_rust_path(&base, rest) {
+                found.push(path);
+            }
+        }
+    }
+
+    found
+}
+
+fn expand_use_tree(tree: &str) -> Vec<String> {
+    let Some(open) = tree.find('{') else {
+        return vec![tree.to_string()];
+    };
+    let Some(inner) = tree[open + 1..].strip_suffix('}') else {
+        return vec![tree.to_string()];
+    };
+    let prefix = &tree[..open];
+
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&inner[start..]);
+
+    items
+        .into_iter()
+        .filter(|item| !item.is_empty())
+        .flat_map(|item| expand_use_tree(&format!("{}{}", prefix, item)))
+        .collect()
+}
+
+fn rust_module_dir(file: &Path) -> PathBuf {
+    let parent = file.parent().unwrap_or(Path::new("")).to_path_buf();
+    match file.file_name().and_then(|n| n.to_str()) {
+        Some("main.rs" | "lib.rs" | "mod.rs") => parent,
+        _ => match file.file_stem() {
+            Some(stem) => parent.join(stem),
+            None => parent,
+        },
+    }
+}
+
+fn rust_crate_root(file: &Path) -> Option<PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(|dir| dir.join("src"))
+}
+
+fn rust_module_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    [
+        dir.join(format!("{}.rs", name)),
+        dir.join(name).join("mod.rs"),
+    ]
+    .into_iter()
+    .find(|p| p.is_file())
+}
+
+fn resolve_rust_path(base: &Path, segments: &[&str]) -> Option<PathBuf> {
+    let mut dir = base.to_path_buf();
+    let mut deepest = None;
+    for segment in segments {
+        match rust_module_file(&dir, segment) {
+            Some(path) => {
+                deepest = Some(path);
+                dir = dir.join(segment);
+            }
+            None => break,
+        }
+    }
+    deepest
+}
+
+fn python_dependencies(file: &Path, content: &str) -> Vec<PathBuf> {
+    let content = strip_line_comments(content, "#");
+    let dir = file.parent().unwrap_or(Path::new("")).to_path_buf();
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut found = Vec::new();
+
+    // Only a parenthesized list continues onto the next lines.
+    let from_re =
+        Regex::new(r"(?m)^\s*from\s+(\.*)([\w.]*)\s+import\s+(?:\(([\w\s,*]+)\)|([\w \t,*]+))")
+            .unwrap();
+    for cap in from_re.captures_iter(&content) {
+        let dots = cap[1].len();
+        let module = &cap[2];
+        let names: Vec<&str> = cap
+            .get(3)
+            .or(cap.get(4))
+            .map_or("", |names| names.as_str())
+            .split(',')
+            .filter_map(|n| n.split_whitespace().next())
+            .collect();
+
+        let bases = if dots > 0 {
+            let mut base = dir.clone();
+            for _ in 1..dots {
+                base.pop();
+            }
+            vec![base]
+        } else {
+            vec![dir.clone(), cwd.clone()]
+        };
+
+        for base in bases {
+            let module_dir = module
+                .split('.')
+                .filter(|s| !s.is_empty())
+                .fold(base, |acc, seg| acc.join(seg));
+            if !module.is_empty() {
+                if let Some(path) = python_module_file(&module_dir) {
+                    found.push(path);
+                }
+            }
+            for name in &names {
+                if let Some(path) = python_module_file(&module_dir.join(name)) {
+                    found.push(path);
+                }
+            }
+        }
+    }
+
+    let import_re = Regex::new(r"(?m)^\s*import\s+([\w.,\s]+)").unwrap();
+    for cap in import_re.captures_iter(&content) {
+        for module in cap[1].split(',') {
+            let Some(module) = module.split_whitespace().next() else {
+                continue;
+            };
+            for base in [&dir, &cwd] {
+                let module_path = module
+                    .split('.')
+                    .fold(base.clone(), |acc, seg| acc.join(seg));
+                if let Some(path) = python_module_file(&module_path) {
+                    found.push(path);
+                }
+            }
+        }
+    }
+
+    found
+}
+
+fn python_module_file(module: &Path) -> Option<PathBuf> {
+    [module.with_extension("py"), module.join("__init__.py")]
+        .into_iter()
+        .find(|p| p.is_file())
+}
+
+fn js_dependencies(file: &Path, content: &str) -> Vec<PathBuf> {
+    let dir = file.parent().unwrap_or(Path::new(""));
+    let import_re =
+        Regex::new(r#"(?:\bfrom|\bimport|\brequire\s*\()\s*\(?\s*['"](\.{1,2}/[^'"]+)['"]"#)
+            .unwrap();
+
+    import_re
+        .captures_iter(content)
+        .filter_map(|cap| resolve_js_path(&dir.join(&cap[1])))
+        .collect()
+}
+
+fn resolve_js_path(base: &Path) -> Option<PathBuf> {
+    const EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+
+    if base.is_file() {
+        return Some(base.to_path_buf());
+    }
+    let name = base.file_name()?.to_str()?;
+    EXTENSIONS
+        .iter()
+        .map(|ext| base.with_file_name(format!("{}.{}", name, ext)))
+        .chain(
+            EXTENSIONS
+                .iter()
+                .map(|ext| base.join(format!("index.{}", ext))),
+        )
+        .find(|p| p.is_file())
+}
+
+fn strip_line_comments(content: &str, marker: &str) -> String {
+    content
+        .lines()
+        .map(|line| match line.find(marker) {
+            Some(idx) if line[..idx].trim().is_empty() => "",
+            _ => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+
+use crate::provider::Provider;
+
+/// Pastes the prompt into the provider's macOS desktop app, pressing return if `submit` is set.
+#[cfg(target_os = "macos")]
+pub fn submit(provider: &dyn Provider, prompt: &str, submit: bool) -> Result<()> {
+    use anyhow::Context;
+    use std::process::Command;
+
+    use crate::clipboard::{Clipboard, SystemClipboard};
+
+    let app = provider.app().with_context(|| {
+        format!(
+            "Provider {} has no desktop app; set app in its config section",
+            provider.name()
+        )
+    })?;
+
+    SystemClipboard.set_text(prompt)?;
+
+    let mut script = vec![
+        format!(
+            "tell application \"{}\" to activate",
+            app.replace('"', "\\\"")
+        ),
+        "delay 1".to_string(),
+        "tell application \"System Events\" to keystroke \"v\" using command down".to_string(),
+    ];
+    if submit {
+        script.push("delay 0.3".to_string());
+        script.push("tell application \"System Events\" to key code 36".to_string());
+    }
+
+    let mut cmd = Command::new("osascript");
+    for line in &script {
+        cmd.arg("-e").arg(line);
+    }
+    let output = cmd.output().context("Failed to run osascript")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "osascript failed (grant Accessibility access to your terminal?): {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn submit(provider: &dyn Provider, _prompt: &str, _submit: bool) -> Result<()> {
+    let app = provider.app().unwrap_or(provider.name());
+    anyhow::bail!("Driving the {} desktop app is only supported on macOS", app)
+}
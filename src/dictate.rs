@@ -0,0 +1,90 @@
+use std::process::{Child, Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::DictateConfig;
+use crate::output;
+
+/// Records from the microphone until Enter is pressed and returns the transcribed text.
+pub fn prompt(config: &DictateConfig) -> Result<String> {
+    if let Some(command) = &config.command {
+        let output = command_for(command, None)?
+            .stderr(Stdio::inherit())
+            .output()
+            .with_context(|| format!("Failed to run dictation command {:?}", command))?;
+        if !output.status.success() {
+            bail!("Dictation command {:?} failed", command);
+        }
+        return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+    }
+
+    let file = std::env::temp_dir().join(format!("search-dictation-{}.wav", std::process::id()));
+    let file_arg = file.display().to_string();
+    let text = record(config.record(), &file_arg)
+        .and_then(|()| transcribe(config.transcribe(), &file_arg));
+    let _ = std::fs::remove_file(&file);
+    text
+}
+
+fn record(command: &str, file: &str) -> Result<()> {
+    let mut recorder = command_for(command, Some(file))?
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run recorder {:?}; set dictate.record", command))?;
+
+    eprint!("Recording, press Enter to stop ");
+    let read = std::io::stdin().read_line(&mut String::new());
+    stop(&mut recorder);
+    eprintln!();
+    read.context("Failed to read from the terminal")?;
+    Ok(())
+}
+
+/// Interrupts the recorder rather than killing it, so it finishes writing the file.
+fn stop(recorder: &mut Child) {
+    #[cfg(unix)]
+    // SAFETY: kill(2) only sends a signal to the child we spawned.
+    unsafe {
+        libc::kill(recorder.id() as libc::pid_t, libc::SIGINT);
+    }
+    #[cfg(not(unix))]
+    let _ = recorder.kill();
+    let _ = recorder.wait();
+}
+
+fn transcribe(command: &str, file: &str) -> Result<String> {
+    let progress = output::progress("Transcribing");
+    let output = command_for(command, Some(file))?
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {:?}; set dictate.transcribe", command))?;
+    drop(progress);
+    if !output.status.success() {
+        bail!(
+            "Transcription failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    // whisper.cpp prints one segment per line.
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+fn command_for(template: &str, file: Option<&str>) -> Result<Command> {
+    let mut words = template.split_whitespace().map(|word| match file {
+        Some(file) => word.replace("{file}", file),
+        None => word.to_string(),
+    });
+    let program = words.next().context("dictation command is empty")?;
+    let mut command = Command::new(program);
+    command.args(words);
+    Ok(command)
+}
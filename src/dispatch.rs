@@ -0,0 +1,140 @@
+use std::io::IsTerminal;
+
+use crate::browser::{Browser, BrowserOptions, SystemBrowser};
+use crate::clipboard::{Clipboard, SystemClipboard};
+use crate::config::WebDriverConfig;
+use crate::error::SearchError;
+use crate::provider::Provider;
+use crate::{desktop, output, webdriver};
+
+/// Sends a composed query to a provider.
+pub trait Dispatcher {
+    /// Returns the URL the query was sent to, or the provider's home page if it wasn't sent by URL.
+    fn dispatch(&self, provider: &dyn Provider, query: &str) -> Result<String, SearchError>;
+}
+
+/// Opens, prints, or copies the provider's query URL.
+pub struct UrlDispatcher {
+    pub open: bool,
+    pub print_url: bool,
+    pub copy_url: bool,
+    pub qr: bool,
+    pub browser: BrowserOptions,
+}
+
+impl UrlDispatcher {
+    /// Dispatches through the given browser and clipboard instead of the system ones.
+    pub fn dispatch_with(
+        &self,
+        provider: &dyn Provider,
+        query: &str,
+        browser: &dyn Browser,
+        clipboard: &dyn Clipboard,
+    ) -> Result<String, SearchError> {
+        let url = provider.query_url(query);
+
+        if self.print_url {
+            println!("{}", url);
+        } else if !self.open {
+            print_link(&url, provider);
+        }
+
+        if self.qr {
+            print_qr(&url)?;
+        }
+
+        if self.open {
+            if !provider.takes_query() {
+                browser.open(provider.home())?;
+                output::note("The prompt has been copied to the clipboard; paste it into the conversation now.");
+                clipboard.set_text(query)?;
+                return Ok(url);
+            }
+            if url.len() <= provider.max_url_length() {
+                browser.open(&url)?;
+            } else {
+                browser.open(provider.home())?;
+                output::warn(format!(
+                    "Prompt is too long for a URL ({} > {} characters); it has been copied to the clipboard, paste it into the new chat now.",
+                    url.len(),
+                    provider.max_url_length()
+                ));
+                clipboard.set_text(query)?;
+                return Ok(url);
+            }
+        }
+
+        if self.copy_url {
+            clipboard.set_text(&url)?;
+        }
+
+        Ok(url)
+    }
+}
+
+impl Dispatcher for UrlDispatcher {
+    fn dispatch(&self, provider: &dyn Provider, query: &str) -> Result<String, SearchError> {
+        self.dispatch_with(
+            provider,
+            query,
+            &SystemBrowser(&self.browser),
+            &SystemClipboard,
+        )
+    }
+}
+
+/// Types the query into the provider's chat box through a WebDriver server.
+pub struct WebDriverDispatcher<'a> {
+    pub config: &'a WebDriverConfig,
+}
+
+impl Dispatcher for WebDriverDispatcher<'_> {
+    fn dispatch(&self, provider: &dyn Provider, query: &str) -> Result<String, SearchError> {
+        webdriver::submit(self.config, provider, query)?;
+        Ok(provider.home().to_string())
+    }
+}
+
+/// Pastes the query into the provider's desktop app.
+pub struct DesktopDispatcher {
+    pub submit: bool,
+}
+
+impl Dispatcher for DesktopDispatcher {
+    fn dispatch(&self, provider: &dyn Provider, query: &str) -> Result<String, SearchError> {
+        desktop::submit(provider, query, self.submit)?;
+        Ok(provider.home().to_string())
+    }
+}
+
+fn print_link(url: &str, provider: &dyn Provider) {
+    if std::io::stdout().is_terminal() {
+        let label = format!("Open in {} ({} character URL)", provider.name(), url.len());
+        println!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, label);
+    } else {
+        println!("{}", url);
+    }
+}
+
+/// Largest payload a QR code holds in byte mode at the lowest error correction level.
+const QR_CAPACITY: usize = 2_953;
+
+fn print_qr(url: &str) -> Result<(), SearchError> {
+    use qrcode::render::unicode::Dense1x2;
+    use qrcode::{EcLevel, QrCode};
+
+    let code = QrCode::with_error_correction_level(url, EcLevel::L).map_err(|_| {
+        SearchError::ContextTooLarge {
+            what: "URL for the QR code",
+            size: url.len(),
+            limit: QR_CAPACITY,
+        }
+    })?;
+    let rendered = code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build();
+    println!("{}", rendered);
+    Ok(())
+}
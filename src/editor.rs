@@ -0,0 +1,63 @@
+use std::io::Read;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+const HEADER: &str = "# Write the prompt above this line; an empty prompt sends nothing.";
+
+/// Asks for a prompt in `$VISUAL` or `$EDITOR`, or line by line on the terminal without one.
+pub fn prompt() -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty());
+    let text = match editor {
+        Some(editor) => in_editor(&editor)?,
+        None => inline()?,
+    };
+    Ok(text.trim().to_string())
+}
+
+fn in_editor(editor: &str) -> Result<String> {
+    let path = std::env::temp_dir().join(format!("search-prompt-{}.md", std::process::id()));
+    std::fs::write(&path, format!("\n\n{}\n", HEADER))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    // Editors like `code --wait` come with arguments.
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(editor);
+    let status = Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("Failed to run {}", editor));
+    let text = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    if !status?.success() {
+        bail!("{} exited with an error; nothing was sent", editor);
+    }
+
+    let text = text.with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(text
+        .lines()
+        .filter(|line| line.trim_end() != HEADER)
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn inline() -> Result<String> {
+    let end = if cfg!(windows) {
+        "Ctrl-Z and Enter"
+    } else {
+        "Ctrl-D on an empty line"
+    };
+    eprintln!(
+        "Enter the prompt, then press {} (set $EDITOR to use an editor):",
+        end
+    );
+    let mut text = String::new();
+    std::io::stdin()
+        .read_to_string(&mut text)
+        .context("Failed to read the prompt")?;
+    Ok(text)
+}
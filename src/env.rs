@@ -0,0 +1,96 @@
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+
+/// Toolchains whose versions usually matter when something works on one machine but not another.
+pub const TOOLS: &[&str] = &[
+    "git", "rustc", "cargo", "node", "npm", "python3", "go", "java", "docker",
+];
+
+/// Variables never shown with a value, even with `values`.
+const SECRET_NAME: &str = r"(?i)(token|secret|password|passwd|key|credential|auth|cookie|session)";
+
+/// OS, shell, tool versions, and the environment variables whose names match `pattern`.
+///
+/// Every name is listed, but values are hidden unless `values` is set.
+pub fn snapshot(pattern: &str, values: bool) -> Result<String> {
+    let pattern = RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .with_context(|| format!("Invalid --env pattern {:?}", pattern))?;
+    let secret = Regex::new(SECRET_NAME).expect("valid regex");
+
+    let mut out = format!("OS: {} {}\n", std::env::consts::OS, std::env::consts::ARCH);
+    if let Some(shell) = shell() {
+        out.push_str(&format!("Shell: {}\n", shell));
+    }
+    let tools = tool_versions(TOOLS);
+    if !tools.is_empty() {
+        out.push_str("Tools:\n");
+        for (tool, version) in tools {
+            out.push_str(&format!("  {}: {}\n", tool, version));
+        }
+    }
+
+    let mut vars: Vec<(String, String)> = std::env::vars_os()
+        .map(|(name, value)| {
+            (
+                name.to_string_lossy().into_owned(),
+                value.to_string_lossy().into_owned(),
+            )
+        })
+        .filter(|(name, _)| pattern.is_match(name))
+        .collect();
+    vars.sort();
+    if !vars.is_empty() {
+        out.push_str("Environment:\n");
+        for (name, value) in vars {
+            if values && !secret.is_match(&name) {
+                out.push_str(&format!("  {}={}\n", name, value));
+            } else {
+                out.push_str(&format!("  {} (set)\n", name));
+            }
+        }
+    }
+    Ok(out.trim_end().to_string())
+}
+
+/// The first line of `--version` for each of `tools` that is installed, in order.
+pub fn tool_versions(tools: &[&str]) -> Vec<(String, String)> {
+    tools
+        .par_iter()
+        .filter_map(|tool| Some((tool.to_string(), version(tool)?)))
+        .collect()
+}
+
+fn shell() -> Option<String> {
+    let shell = std::env::var("SHELL")
+        .or_else(|_| std::env::var("COMSPEC"))
+        .ok()?;
+    Some(match version(&shell) {
+        Some(version) => format!("{} ({})", shell, version),
+        None => shell,
+    })
+}
+
+fn version(program: &str) -> Option<String> {
+    let output = Command::new(program)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Some tools, like older Java, print their version to stderr.
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    let text = String::from_utf8_lossy(&text);
+    let line = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+    Some(line.to_string())
+}
@@ -0,0 +1,66 @@
+use thiserror::Error;
+
+/// Failures that library consumers may want to tell apart.
+#[derive(Debug, Error)]
+pub enum SearchError {
+    #[error("Unsupported provider: {0}")]
+    UnknownProvider(String),
+
+    #[error("{0} has no conversation URL template; pass the full URL to --chat")]
+    NoChatUrl(String),
+
+    #[error("No readable text files found in context paths")]
+    NoContext,
+
+    #[error("{what} is {size} bytes, over the limit of {limit}")]
+    ContextTooLarge {
+        what: &'static str,
+        size: usize,
+        limit: usize,
+    },
+
+    #[error("Clipboard is unavailable: {0}")]
+    ClipboardUnavailable(String),
+
+    #[error("{0}")]
+    BrowserFailed(String),
+
+    #[error("contextualize command failed: {stderr}")]
+    ContextualizeFailed { stderr: String },
+
+    #[error("contextualize timed out after {secs}s: {stderr}")]
+    ContextualizeTimedOut { secs: u64, stderr: String },
+
+    #[error("Interrupted")]
+    Interrupted,
+
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl From<anyhow::Error> for SearchError {
+    /// Recovers a typed error that was passed through `anyhow`, so its kind isn't lost.
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<SearchError>() {
+            Ok(error) => error,
+            Err(error) => SearchError::Other(error),
+        }
+    }
+}
+
+impl SearchError {
+    /// Process exit status for the CLI.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            SearchError::Other(_) => 1,
+            SearchError::UnknownProvider(_) | SearchError::NoChatUrl(_) => 2,
+            SearchError::NoContext | SearchError::ContextTooLarge { .. } => 3,
+            SearchError::ClipboardUnavailable(_) => 4,
+            SearchError::BrowserFailed(_) => 5,
+            SearchError::ContextualizeFailed { .. } | SearchError::ContextualizeTimedOut { .. } => {
+                6
+            }
+            SearchError::Interrupted => 130,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::format::Paste;
+use crate::sanitize::sanitize;
+
+/// Man pages can be huge, so only the sections that describe the command line are sent.
+const MAX_MAN_BYTES: usize = 12_000;
+const HELP_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn prompt(command: &[String], how: Option<&str>) -> String {
+    match how {
+        Some(task) => format!(
+            "Using `{}`, what is the right invocation to {}? Give the command first, then explain each part briefly.",
+            command.join(" "),
+            task
+        ),
+        None => format!(
+            "Explain in plain English what `{}` does, going through each argument and flag. \
+Mention anything surprising or dangerous about it.",
+            command.join(" ")
+        ),
+    }
+}
+
+/// The command's `--help` output and the relevant parts of its man page.
+pub fn gather(command: &[String]) -> Result<Vec<Paste>> {
+    let Some(program) = command.first() else {
+        anyhow::bail!(
+            "Pass the command to explain after --, e.g. search explain -- tar -xzf x.tgz"
+        );
+    };
+    let subcommand = command.get(1).filter(|arg| is_word(arg));
+    let flags: Vec<&str> = command[1..]
+        .iter()
+        .filter(|arg| arg.starts_with('-'))
+        .map(|arg| arg.split('=').next().unwrap_or(arg))
+        .collect();
+
+    // Only run `program sub --help` for a listed subcommand; `rm notes --help` would delete notes
+    // wherever getopt stops at the first operand.
+    let top = capture(program, &["--help"]);
+    let help = subcommand
+        .filter(|sub| top.as_deref().is_some_and(|help| lists(help, sub)))
+        .and_then(|sub| capture(program, &[sub, "--help"]))
+        .or(top)
+        .or_else(|| capture(program, &["-h"]));
+    let man = subcommand
+        .and_then(|sub| capture("man", &[&format!("{}-{}", program, sub)]))
+        .or_else(|| capture("man", &[program]))
+        .map(|page| relevant_sections(&sanitize(&page), &flags));
+
+    if help.is_none() && man.is_none() {
+        anyhow::bail!("Found neither --help output nor a man page for {}", program);
+    }
+    let pastes = [("help", help), ("man", man)]
+        .into_iter()
+        .filter_map(|(source, content)| content.map(|content| Paste { source, content }))
+        .collect();
+    Ok(pastes)
+}
+
+/// Whether help output has an indented entry for the subcommand, like `  commit   Record changes`.
+fn lists(help: &str, subcommand: &str) -> bool {
+    help.lines()
+        .filter(|line| line.starts_with(char::is_whitespace))
+        .filter_map(|line| line.split_whitespace().next())
+        .any(|word| word.trim_end_matches([',', ':']) == subcommand)
+}
+
+fn is_word(arg: &str) -> bool {
+    !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !arg.starts_with('-')
+}
+
+/// Runs a command with pagers disabled, returning its output if it printed anything.
+fn capture(program: &str, args: &[&str]) -> Option<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .env("PAGER", "cat")
+        .env("MANPAGER", "cat")
+        .env("GIT_PAGER", "cat")
+        .env("MANWIDTH", "100")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    // Read while waiting, or a page larger than the pipe buffer blocks the child.
+    let mut stdout = child.stdout.take()?;
+    let mut stderr = child.stderr.take()?;
+    let stdout = std::thread::spawn(move || {
+        let mut text = Vec::new();
+        let _ = stdout.read_to_end(&mut text);
+        text
+    });
+    let stderr = std::thread::spawn(move || {
+        let mut text = Vec::new();
+        let _ = stderr.read_to_end(&mut text);
+        text
+    });
+
+    // Some programs ignore --help and wait for input or run, so don't wait forever.
+    let deadline = Instant::now() + HELP_TIMEOUT;
+    while child.try_wait().ok()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    // Many tools print usage to stderr, or exit non-zero even for --help.
+    let mut text = String::from_utf8_lossy(&stdout.join().unwrap_or_default()).into_owned();
+    if text.trim().is_empty() {
+        text = String::from_utf8_lossy(&stderr.join().unwrap_or_default()).into_owned();
+    }
+    let text = text.trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// NAME, SYNOPSIS, the start of DESCRIPTION, and the entries for the flags in use.
+fn relevant_sections(page: &str, flags: &[&str]) -> String {
+    let mut out = String::new();
+    let mut section = "";
+    let mut in_flag = false;
+    let mut description_lines = 0;
+
+    for line in page.lines() {
+        if !line.is_empty() && !line.starts_with(char::is_whitespace) {
+            section = line.trim();
+            in_flag = false;
+            if matches!(section, "NAME" | "SYNOPSIS" | "DESCRIPTION") {
+                out.push_str(line);
+                out.push('\n');
+            }
+            continue;
+        }
+
+        let keep = match section {
+            "NAME" | "SYNOPSIS" => true,
+            "DESCRIPTION" => {
+                description_lines += 1;
+                description_lines <= 20
+            }
+            _ => {
+                let trimmed = line.trim_start();
+                let indent = line.len() - trimmed.len();
+                if trimmed.starts_with('-') && indent <= 8 {
+                    in_flag = flags.iter().any(|flag| {
+                        trimmed
+                            .split([',', ' ', '='])
+                            .any(|word| word.trim() == *flag)
+                    });
+                }
+                in_flag
+            }
+        };
+        if keep {
+            out.push_str(line);
+            out.push('\n');
+        }
+        if out.len() >= MAX_MAN_BYTES {
+            break;
+        }
+    }
+    out.trim_end().to_string()
+}
@@ -0,0 +1,314 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::config::Templates;
+use crate::context::{format_size, ContextFile};
+use crate::provider::Style;
+
+/// Where the prompt goes relative to the context.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Order {
+    PromptFirst,
+    #[default]
+    ContextFirst,
+}
+
+#[derive(Clone)]
+pub struct Paste {
+    pub source: &'static str,
+    pub content: String,
+}
+
+pub struct FormatOptions {
+    pub style: Style,
+    pub line_numbers: bool,
+    pub templates: Templates,
+    pub order: Order,
+    /// Context sections by source name, e.g. ["diff", "files", "clipboard"]; unlisted ones follow
+    pub source_order: Vec<String>,
+}
+
+/// Renders pasted text, files, and the prompt into a single query.
+pub trait Formatter {
+    fn format(&self, pastes: &[Paste], files: &[ContextFile], prompt: &str) -> String;
+}
+
+impl Formatter for FormatOptions {
+    fn format(&self, pastes: &[Paste], files: &[ContextFile], prompt: &str) -> String {
+        format_content(pastes, files, prompt, self)
+    }
+}
+
+pub fn format_content(
+    pastes: &[Paste],
+    files: &[ContextFile],
+    query: &str,
+    options: &FormatOptions,
+) -> String {
+    let pastes: Vec<&Paste> = pastes.iter().filter(|p| !p.content.is_empty()).collect();
+
+    if options.style == Style::Keywords {
+        let text = pastes
+            .iter()
+            .map(|p| p.content.as_str())
+            .chain(files.iter().map(|f| f.content.as_str()));
+        let text = match options.order {
+            Order::PromptFirst => std::iter::once(query).chain(text).collect::<Vec<_>>(),
+            Order::ContextFirst => text.chain(std::iter::once(query)).collect(),
+        };
+        return collapse_keywords(&text.join(" "));
+    }
+
+    let context = templated_context(&pastes, files, query, options);
+
+    if context.is_empty() {
+        query.to_string()
+    } else if query.is_empty() {
+        context
+    } else if options.order == Order::PromptFirst {
+        format!("{}\n{}", query, context)
+    } else {
+        format!("{}\n{}", context, query)
+    }
+}
+
+fn templated_context(
+    pastes: &[&Paste],
+    files: &[ContextFile],
+    query: &str,
+    options: &FormatOptions,
+) -> String {
+    let paste_template = options.templates.paste.as_deref();
+    let file_template = options.templates.file.as_deref();
+
+    let file_section = match file_template {
+        Some(template) => files
+            .iter()
+            .map(|file| {
+                let path = file.path.display().to_string();
+                let size = format_size(file.size);
+                let note = file_note(file).unwrap_or_default();
+                let content = file_body(file, options.line_numbers);
+                render(
+                    template,
+                    &[
+                        ("source", "file"),
+                        ("path", &path),
+                        ("lang", file.lang.unwrap_or("")),
+                        ("size", &size),
+                        ("note", &note),
+                        ("query", query),
+                        ("content", &content),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        None => match options.style {
+            Style::Xml => xml_files(files, options.line_numbers),
+            _ => wrap_files(files, options.line_numbers),
+        },
+    };
+
+    let mut sections: Vec<(&str, String)> = pastes
+        .iter()
+        .map(|paste| {
+            let section = match paste_template {
+                Some(template) => render(
+                    template,
+                    &[
+                        ("source", paste.source),
+                        ("path", ""),
+                        ("query", query),
+                        ("content", &paste.content),
+                    ],
+                ),
+                None if options.style == Style::Xml => {
+                    format!("<paste>\n{}\n</paste>", paste.content)
+                }
+                None => wrap_paste(&paste.content),
+            };
+            (paste.source, section)
+        })
+        .collect();
+
+    // The repository header introduces everything else, whatever the order.
+    let repo = sections
+        .iter()
+        .take_while(|(source, _)| *source == "repo")
+        .count();
+    if !file_section.is_empty() {
+        match options.style {
+            Style::Xml => sections.insert(repo, ("files", file_section)),
+            _ => sections.push(("files", file_section)),
+        }
+    }
+    if !options.source_order.is_empty() {
+        sections.sort_by_key(|(source, _)| {
+            let position = options
+                .source_order
+                .iter()
+                .position(|name| name == source)
+                .unwrap_or(usize::MAX);
+            (*source != "repo", position)
+        });
+    }
+    sections
+        .into_iter()
+        .map(|(_, section)| section)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn render(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|(_, value)| (end, *value))
+        });
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn xml_files(files: &[ContextFile], line_numbers: bool) -> String {
+    if files.is_empty() {
+        return String::new();
+    }
+
+    let documents: Vec<String> = files
+        .iter()
+        .map(|file| {
+            let mut attrs = format!(
+                "path=\"{}\" size=\"{}\"",
+                escape_attr(&file.path.display().to_string()),
+                format_size(file.size)
+            );
+            if let Some(lang) = file.lang {
+                attrs.push_str(&format!(" language=\"{}\"", lang));
+            }
+            if let Some(note) = file_note(file) {
+                attrs.push_str(&format!(" note=\"{}\"", escape_attr(&note)));
+            }
+            format!(
+                "<document {}>\n{}\n</document>",
+                attrs,
+                file_body(file, line_numbers)
+            )
+        })
+        .collect();
+    format!("<documents>\n{}\n</documents>", documents.join("\n"))
+}
+
+fn collapse_keywords(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| "`*#>|".contains(c)))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape_attr(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
+fn wrap_paste(content: &str) -> String {
+    let fence = fence_for(content);
+    format!("{}paste\n{}\n{}", fence, content, fence)
+}
+
+pub fn fence_for(content: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn file_note(file: &ContextFile) -> Option<String> {
+    if let Some(note) = &file.note {
+        Some(note.clone())
+    } else if file.truncated {
+        Some(format!(
+            "truncated to first {}",
+            format_size(file.content.len())
+        ))
+    } else {
+        None
+    }
+}
+
+fn file_header(file: &ContextFile) -> String {
+    match file_note(file) {
+        Some(note) => format!(
+            "{} ({}, {})",
+            file.path.display(),
+            format_size(file.size),
+            note
+        ),
+        None => format!("{} ({})", file.path.display(), format_size(file.size)),
+    }
+}
+
+fn file_body(file: &ContextFile, line_numbers: bool) -> String {
+    if line_numbers {
+        number_lines(&file.content)
+    } else {
+        file.content.trim_end_matches('\n').to_string()
+    }
+}
+
+fn number_lines(content: &str) -> String {
+    let width = content.lines().count().to_string().len();
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| format!("{:>width$}: {}", i + 1, line, width = width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn wrap_files(files: &[ContextFile], line_numbers: bool) -> String {
+    files
+        .iter()
+        .map(|file| {
+            let fence = fence_for(&file.content);
+            format!(
+                "{}\n{}{}\n{}\n{}",
+                file_header(file),
+                fence,
+                file.lang.unwrap_or(""),
+                file_body(file, line_numbers),
+                fence
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
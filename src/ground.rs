@@ -0,0 +1,178 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use regex::Regex;
+use serde_json::Value;
+use ureq::Agent;
+
+use crate::config::GroundConfig;
+use crate::output;
+use crate::provider::Encoding;
+
+const MAX_PAGE_BYTES: u64 = 2 * 1024 * 1024;
+
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+}
+
+/// Searches the configured engine for the prompt and returns the top results' text, numbered
+/// for citation.
+pub fn context(config: &GroundConfig, query: &str) -> Result<String> {
+    let agent: Agent = Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(config.timeout_secs())))
+        .build()
+        .into();
+
+    let progress = output::progress("Searching the web");
+    let results = search(&agent, config, query)?;
+    drop(progress);
+    if results.is_empty() {
+        anyhow::bail!("The web search for {:?} returned no results", query);
+    }
+
+    let progress = output::progress(format!("Fetching {} result(s)", results.len()));
+    let pages: Vec<Option<String>> = results
+        .par_iter()
+        .map(|result| match fetch(&agent, &result.url) {
+            Ok(text) => Some(text),
+            Err(e) => {
+                output::warn(format!("Skipping {}: {:#}", result.url, e));
+                None
+            }
+        })
+        .collect();
+    drop(progress);
+
+    let mut sections = vec![
+        "Web search results for this question. Cite them by number where you use them.".to_string(),
+    ];
+    for (result, page) in results.iter().zip(pages) {
+        let Some(mut text) = page.filter(|text| !text.is_empty()) else {
+            continue;
+        };
+        if text.len() > config.max_bytes() {
+            let mut end = config.max_bytes();
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text.truncate(end);
+        }
+        // Numbered by position among the pages that loaded, so citations have no gaps.
+        sections.push(format!(
+            "[{}] {} <{}>\n{}",
+            sections.len(),
+            result.title,
+            result.url,
+            text
+        ));
+    }
+    if sections.len() == 1 {
+        anyhow::bail!("None of the web search results could be fetched");
+    }
+    Ok(sections.join("\n\n"))
+}
+
+fn search(agent: &Agent, config: &GroundConfig, query: &str) -> Result<Vec<SearchResult>> {
+    let template = config
+        .url
+        .as_deref()
+        .context("Set ground.url in the config to a search API URL with a {query} placeholder")?;
+    let url = template.replace("{query}", &Encoding::Form.encode(query, ""));
+
+    let mut request = agent.get(&url).header("Accept", "application/json");
+    if let Some(name) = &config.api_key_env {
+        let key = std::env::var(name).with_context(|| format!("${} is not set", name))?;
+        request = request.header(config.api_key_header(), &key);
+    }
+    let body: Value = request
+        .call()
+        .with_context(|| format!("Failed to reach the search API at {}", template))?
+        .body_mut()
+        .read_json()
+        .context("Failed to parse the search API response")?;
+
+    Ok(results(&body).into_iter().take(config.results()).collect())
+}
+
+/// Results from the response shapes of common search APIs: SearXNG, Brave, Google Programmable
+/// Search, and Bing.
+fn results(body: &Value) -> Vec<SearchResult> {
+    let lists = [
+        (&body["results"], "url", "title"),
+        (&body["web"]["results"], "url", "title"),
+        (&body["items"], "link", "title"),
+        (&body["webPages"]["value"], "url", "name"),
+    ];
+    let Some((list, url_key, title_key)) = lists.iter().find(|(list, ..)| list.is_array()) else {
+        return Vec::new();
+    };
+    list.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let url = item[url_key].as_str()?;
+            url.starts_with("http").then(|| SearchResult {
+                title: item[title_key].as_str().unwrap_or(url).trim().to_string(),
+                url: url.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn fetch(agent: &Agent, url: &str) -> Result<String> {
+    let mut response = agent.get(url).call()?;
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_lowercase();
+    if !content_type.is_empty() && !content_type.starts_with("text/") {
+        anyhow::bail!("not a web page ({})", content_type);
+    }
+    let body = response
+        .body_mut()
+        .with_config()
+        .limit(MAX_PAGE_BYTES)
+        .read_to_string()?;
+    Ok(if content_type.starts_with("text/plain") {
+        body.trim().to_string()
+    } else {
+        page_text(&body)
+    })
+}
+
+static HIDDEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?is)<script\b.*?</script>|<style\b.*?</style>|<noscript\b.*?</noscript>|<svg\b.*?</svg>|<head\b.*?</head>|<nav\b.*?</nav>|<footer\b.*?</footer>|<!--.*?-->",
+    )
+    .expect("valid regex")
+});
+static BLOCKS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)</?(?:p|div|br|li|h[1-6]|tr|section|article|pre|blockquote)\b[^>]*>")
+        .expect("valid regex")
+});
+static TAGS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").expect("valid regex"));
+
+/// Visible text of an HTML page, one block per line.
+fn page_text(html: &str) -> String {
+    let text = HIDDEN.replace_all(html, " ");
+    let text = BLOCKS.replace_all(&text, "\n");
+    let text = TAGS.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
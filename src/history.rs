@@ -0,0 +1,466 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chrono::{DateTime, Duration, Local, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, HistoryConfig};
+use crate::format;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    pub id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub provider: String,
+    pub prompt: String,
+    pub sources: Vec<String>,
+    pub composed: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// Library template the prompt was wrapped in, from --template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// The provider's reply, known only for imported conversations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+    /// Recorded by `search import` rather than sent from here
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub imported: bool,
+}
+
+impl Entry {
+    /// A new entry stamped with the current time; `record` assigns its id.
+    pub fn new(provider: &str, prompt: &str, composed: &str, url: &str) -> Self {
+        Self {
+            id: 0,
+            timestamp: Utc::now(),
+            provider: provider.to_string(),
+            prompt: prompt.to_string(),
+            sources: Vec::new(),
+            composed: composed.to_string(),
+            url: url.to_string(),
+            saved: None,
+            session: None,
+            template: None,
+            answer: None,
+            imported: false,
+        }
+    }
+
+    pub fn local_time(&self) -> String {
+        self.timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    pub fn summary(&self) -> String {
+        let prompt = if self.prompt.is_empty() {
+            "(no prompt)"
+        } else {
+            self.prompt.as_str()
+        };
+        let mut line: String = prompt.chars().take(72).collect();
+        if prompt.chars().count() > 72 {
+            line.push('…');
+        }
+        if !self.sources.is_empty() {
+            line.push_str(&format!(" [{}]", self.sources.join(", ")));
+        }
+        line
+    }
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("history.jsonl"))
+}
+
+pub fn load(config: &HistoryConfig) -> Result<Vec<Entry>> {
+    let Some(path) = history_path() else {
+        return Ok(Vec::new());
+    };
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read history file {}", path.display()))?;
+    let mut cipher = None;
+    let mut entries = Vec::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let json = if line.starts_with('{') {
+            line.to_string()
+        } else {
+            if cipher.is_none() {
+                cipher = Some(load_cipher(config, false)?);
+            }
+            decrypt(cipher.as_ref().unwrap(), line)?
+        };
+        if let Ok(entry) = serde_json::from_str(&json) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+pub fn find(config: &HistoryConfig, id: u64) -> Result<Entry> {
+    load(config)?
+        .into_iter()
+        .find(|entry| entry.id == id)
+        .with_context(|| format!("No history entry with id {}", id))
+}
+
+pub fn find_recent(config: &HistoryConfig, composed: &str, hours: u64) -> Result<Option<Entry>> {
+    let since = Utc::now() - Duration::hours(hours.try_into().unwrap_or(i64::MAX));
+    Ok(load(config)?
+        .into_iter()
+        .rev()
+        .filter(|entry| !entry.imported)
+        .take_while(|entry| entry.timestamp >= since)
+        .find(|entry| entry.composed == composed))
+}
+
+/// The last query sent from here, optionally within a session.
+pub fn last(config: &HistoryConfig, session: Option<&str>) -> Result<Option<Entry>> {
+    Ok(load(config)?.into_iter().rfind(|entry| {
+        !entry.imported && session.is_none_or(|session| entry.session.as_deref() == Some(session))
+    }))
+}
+
+pub fn record(config: &HistoryConfig, entry: Entry) -> Result<Entry> {
+    let mut recorded = record_all(config, vec![entry])?;
+    Ok(recorded.remove(0))
+}
+
+/// Appends entries in order, assigning consecutive ids.
+pub fn record_all(config: &HistoryConfig, mut entries: Vec<Entry>) -> Result<Vec<Entry>> {
+    let path = history_path().context("Could not determine a data directory for history")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let mut id = load(config)?.iter().map(|e| e.id).max().unwrap_or(0);
+    let mut text = String::new();
+    for entry in &mut entries {
+        id += 1;
+        entry.id = id;
+        text.push_str(&encode(config, entry)?);
+        text.push('\n');
+    }
+
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("Failed to open history file {}", path.display()))?;
+    file.write_all(text.as_bytes())
+        .with_context(|| format!("Failed to write history file {}", path.display()))?;
+
+    Ok(entries)
+}
+
+/// Removes entries recorded before `before`, returning how many were dropped.
+pub fn purge(config: &HistoryConfig, before: DateTime<Utc>) -> Result<usize> {
+    let Some(path) = history_path().filter(|path| path.exists()) else {
+        return Ok(0);
+    };
+
+    let entries = load(config)?;
+    let (dropped, kept): (Vec<Entry>, Vec<Entry>) = entries
+        .into_iter()
+        .partition(|entry| entry.timestamp < before);
+
+    let mut text = String::new();
+    for entry in &kept {
+        text.push_str(&encode(config, entry)?);
+        text.push('\n');
+    }
+    // Replace the file whole, so a crash midway leaves the old history rather than part of it.
+    let temp = path.with_extension("jsonl.tmp");
+    let _ = std::fs::remove_file(&temp);
+    write_private(&temp, &text)
+        .and_then(|()| std::fs::rename(&temp, &path))
+        .with_context(|| format!("Failed to write history file {}", path.display()))?;
+
+    Ok(dropped.len())
+}
+
+fn encode(config: &HistoryConfig, entry: &Entry) -> Result<String> {
+    let json = serde_json::to_string(entry)?;
+    if !config.encrypt {
+        return Ok(json);
+    }
+    seal(config, &json)
+}
+
+/// Encrypts text with the history key, as hex.
+pub(crate) fn seal(config: &HistoryConfig, text: &str) -> Result<String> {
+    let cipher = load_cipher(config, true)?;
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, text.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt history entry"))?;
+    Ok(to_hex(&[nonce.as_slice(), &ciphertext].concat()))
+}
+
+/// Decrypts text written by [`seal`].
+pub(crate) fn unseal(config: &HistoryConfig, text: &str) -> Result<String> {
+    decrypt(&load_cipher(config, false)?, text)
+}
+
+fn decrypt(cipher: &ChaCha20Poly1305, line: &str) -> Result<String> {
+    let bytes = from_hex(line).context("History file contains a malformed entry")?;
+    if bytes.len() < NONCE_LEN {
+        anyhow::bail!("History file contains a malformed entry");
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Failed to decrypt history; is the key file correct?"))?;
+    String::from_utf8(plaintext).context("Decrypted history entry is not UTF-8")
+}
+
+const NONCE_LEN: usize = 12;
+
+fn key_path(config: &HistoryConfig) -> Option<PathBuf> {
+    config
+        .key_file
+        .clone()
+        .or_else(|| config::data_dir().map(|dir| dir.join("history.key")))
+}
+
+fn load_cipher(config: &HistoryConfig, create: bool) -> Result<ChaCha20Poly1305> {
+    let path = key_path(config).context("Could not determine a location for the history key")?;
+
+    if !path.exists() {
+        if !create {
+            anyhow::bail!(
+                "History is encrypted but the key file {} does not exist",
+                path.display()
+            );
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        write_private(&path, &to_hex(&key))
+            .with_context(|| format!("Failed to write history key {}", path.display()))?;
+    }
+
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read history key {}", path.display()))?;
+    let key = from_hex(text.trim())
+        .filter(|key| key.len() == 32)
+        .with_context(|| format!("History key {} is not a 32-byte hex key", path.display()))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// One JSON object per line, as stored
+    Jsonl,
+    /// A readable document with each composed prompt
+    Markdown,
+    /// Spreadsheet-friendly rows without the composed prompt
+    Csv,
+}
+
+pub fn export(entries: &[Entry], format: ExportFormat, out: &mut impl Write) -> Result<()> {
+    match format {
+        ExportFormat::Jsonl => {
+            for entry in entries {
+                writeln!(out, "{}", serde_json::to_string(entry)?)?;
+            }
+        }
+        ExportFormat::Markdown => {
+            writeln!(out, "# Search history")?;
+            for entry in entries {
+                writeln!(out)?;
+                writeln!(out, "## {}. {}", entry.id, entry.summary())?;
+                writeln!(out)?;
+                writeln!(out, "- Time: {}", entry.local_time())?;
+                writeln!(out, "- Provider: {}", entry.provider)?;
+                if !entry.sources.is_empty() {
+                    writeln!(out, "- Sources: {}", entry.sources.join(", "))?;
+                }
+                if let Some(session) = &entry.session {
+                    writeln!(out, "- Session: {}", session)?;
+                }
+                if let Some(saved) = &entry.saved {
+                    writeln!(out, "- Saved to: {}", saved.display())?;
+                }
+                writeln!(out, "- URL: <{}>", entry.url)?;
+                writeln!(out)?;
+                let fence = format::fence_for(&entry.composed);
+                writeln!(out, "{}text\n{}\n{}", fence, entry.composed, fence)?;
+                if let Some(answer) = &entry.answer {
+                    writeln!(out)?;
+                    writeln!(out, "### Answer")?;
+                    writeln!(out)?;
+                    writeln!(out, "{}", answer)?;
+                }
+            }
+        }
+        ExportFormat::Csv => {
+            writeln!(
+                out,
+                "id,timestamp,session,provider,prompt,sources,bytes,url"
+            )?;
+            for entry in entries {
+                let row = [
+                    entry.id.to_string(),
+                    entry.timestamp.to_rfc3339(),
+                    entry.session.clone().unwrap_or_default(),
+                    entry.provider.clone(),
+                    entry.prompt.clone(),
+                    entry.sources.join(";"),
+                    entry.composed.len().to_string(),
+                    entry.url.clone(),
+                ];
+                let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                writeln!(out, "{}", row.join(","))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub struct Session {
+    pub name: String,
+    pub queries: usize,
+    pub last: Entry,
+}
+
+/// Copies every query of session `from` into a new session `to`, returning how many were copied.
+pub fn fork(config: &HistoryConfig, from: &str, to: &str) -> Result<usize> {
+    let entries = load(config)?;
+    if entries.iter().any(|e| e.session.as_deref() == Some(to)) {
+        anyhow::bail!("Session {:?} already exists", to);
+    }
+    let copies: Vec<Entry> = entries
+        .into_iter()
+        .filter(|e| e.session.as_deref() == Some(from))
+        .map(|mut e| {
+            e.session = Some(to.to_string());
+            e
+        })
+        .collect();
+    if copies.is_empty() {
+        anyhow::bail!("No session named {:?} in the history", from);
+    }
+    Ok(record_all(config, copies)?.len())
+}
+
+/// Named sessions, most recently used first.
+pub fn sessions(entries: &[Entry]) -> Vec<Session> {
+    let mut sessions: Vec<Session> = Vec::new();
+    for entry in entries {
+        let Some(name) = &entry.session else {
+            continue;
+        };
+        match sessions.iter_mut().find(|s| &s.name == name) {
+            Some(session) => {
+                session.queries += 1;
+                session.last = entry.clone();
+            }
+            None => sessions.push(Session {
+                name: name.clone(),
+                queries: 1,
+                last: entry.clone(),
+            }),
+        }
+    }
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.last.timestamp));
+    sessions
+}
+
+pub fn search<'a>(entries: &'a [Entry], query: &str) -> Vec<&'a Entry> {
+    let query = query.to_lowercase();
+    let mut scored: Vec<(i64, &Entry)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let haystack = format!("{} {}", entry.prompt, entry.sources.join(" ")).to_lowercase();
+            fuzzy_score(&haystack, &query).map(|score| (score, entry))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.id.cmp(&a.1.id)));
+    scored.into_iter().map(|(_, entry)| entry).collect()
+}
+
+fn fuzzy_score(haystack: &str, needle: &str) -> Option<i64> {
+    let needle: Vec<char> = needle.chars().filter(|c| !c.is_whitespace()).collect();
+    if needle.is_empty() {
+        return Some(0);
+    }
+
+    let mut score = 0;
+    let mut matched = 0;
+    let mut previous_match = false;
+    let mut previous_char = ' ';
+    for c in haystack.chars() {
+        if matched < needle.len() && c == needle[matched] {
+            score += 1;
+            if previous_match {
+                score += 5;
+            }
+            if !previous_char.is_alphanumeric() {
+                score += 3;
+            }
+            matched += 1;
+            previous_match = true;
+        } else {
+            previous_match = false;
+        }
+        previous_char = c;
+    }
+
+    (matched == needle.len()).then_some(score)
+}
@@ -0,0 +1,56 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use ureq::Agent;
+
+use crate::config::HookConfig;
+use crate::output;
+
+/// Posts the payload to the configured webhook and pipes it to the configured command.
+///
+/// A failing hook only warns, so it can't stop the rest of a batch.
+pub fn completed(config: &HookConfig, payload: &Value) {
+    if let Some(url) = &config.url {
+        if let Err(e) = post(url, config.timeout_secs(), payload) {
+            output::warn(format!("{:#}", e));
+        }
+    }
+    if let Some(command) = &config.command {
+        if let Err(e) = run(command, payload) {
+            output::warn(format!("{:#}", e));
+        }
+    }
+}
+
+fn post(url: &str, timeout_secs: u64, payload: &Value) -> Result<()> {
+    let agent: Agent = Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(timeout_secs)))
+        .build()
+        .into();
+    agent
+        .post(url)
+        .send_json(payload)
+        .with_context(|| format!("Failed to call the completion webhook {}", url))?;
+    Ok(())
+}
+
+fn run(command: &str, payload: &Value) -> Result<()> {
+    let mut words = command.split_whitespace();
+    let program = words.next().context("hook command is empty")?;
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run hook command {:?}", command))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(payload.to_string().as_bytes())?;
+    drop(stdin);
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("Hook command {:?} exited with {}", command, status);
+    }
+    Ok(())
+}
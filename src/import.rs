@@ -0,0 +1,196 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::history::Entry;
+
+/// Conversations read from a ChatGPT or Claude data export.
+pub struct Imported {
+    pub conversations: usize,
+    pub entries: Vec<Entry>,
+}
+
+struct Message {
+    user: bool,
+    text: String,
+    time: Option<DateTime<Utc>>,
+}
+
+/// Reads `conversations.json` from an export, either the zip as downloaded or the extracted file.
+pub fn read(path: &Path) -> Result<Imported> {
+    let text = if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+    {
+        read_zip(path)?
+    } else {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+    };
+    let conversations: Vec<Value> = serde_json::from_str(&text)
+        .with_context(|| format!("{} is not a conversations export", path.display()))?;
+
+    let mut titles: HashMap<String, usize> = HashMap::new();
+    let mut entries = Vec::new();
+    for conversation in &conversations {
+        let (provider, url, messages) = if conversation.get("mapping").is_some() {
+            chatgpt(conversation)
+                .with_context(|| format!("Malformed conversation in {}", path.display()))?
+        } else if conversation.get("chat_messages").is_some() {
+            claude(conversation)
+        } else {
+            anyhow::bail!("Unrecognized conversation format in {}", path.display());
+        };
+
+        let title = ["title", "name"]
+            .iter()
+            .find_map(|key| conversation[key].as_str())
+            .filter(|title| !title.trim().is_empty())
+            .unwrap_or("Untitled")
+            .trim()
+            .to_string();
+        // Many conversations share titles like "New chat", and sessions are keyed by name.
+        let count = titles.entry(title.clone()).or_insert(0);
+        *count += 1;
+        let session = match *count {
+            1 => title,
+            n => format!("{} ({})", title, n),
+        };
+
+        entries.extend(
+            exchanges(messages)
+                .into_iter()
+                .map(|(prompt, answer, time)| {
+                    let mut entry = Entry::new(provider, &prompt, &prompt, &url);
+                    entry.timestamp = time.unwrap_or(entry.timestamp);
+                    entry.session = Some(session.clone());
+                    entry.answer = answer;
+                    entry.imported = true;
+                    entry
+                }),
+        );
+    }
+
+    entries.sort_by_key(|entry| entry.timestamp);
+    Ok(Imported {
+        conversations: conversations.len(),
+        entries,
+    })
+}
+
+fn read_zip(path: &Path) -> Result<String> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .with_context(|| format!("{} is not a zip archive", path.display()))?;
+    let name = archive
+        .file_names()
+        .find(|name| name.rsplit('/').next() == Some("conversations.json"))
+        .map(str::to_string)
+        .with_context(|| format!("{} has no conversations.json", path.display()))?;
+    let mut text = String::new();
+    archive
+        .by_name(&name)?
+        .read_to_string(&mut text)
+        .with_context(|| format!("Failed to read {} from {}", name, path.display()))?;
+    Ok(text)
+}
+
+/// Pairs each user message with the replies that follow it.
+fn exchanges(messages: Vec<Message>) -> Vec<(String, Option<String>, Option<DateTime<Utc>>)> {
+    let mut exchanges: Vec<(String, Option<String>, Option<DateTime<Utc>>)> = Vec::new();
+    for message in messages {
+        if message.user {
+            exchanges.push((message.text, None, message.time));
+        } else if let Some((_, answer, _)) = exchanges.last_mut() {
+            match answer {
+                Some(answer) => {
+                    answer.push_str("\n\n");
+                    answer.push_str(&message.text);
+                }
+                None => *answer = Some(message.text),
+            }
+        }
+    }
+    exchanges
+}
+
+/// ChatGPT stores a tree of edits and regenerations; the current branch runs from `current_node`
+/// up through the parents.
+fn chatgpt(conversation: &Value) -> Result<(&'static str, String, Vec<Message>)> {
+    let id = conversation["conversation_id"]
+        .as_str()
+        .or(conversation["id"].as_str())
+        .unwrap_or_default();
+    let url = format!("https://chatgpt.com/c/{}", id);
+    let mapping = &conversation["mapping"];
+
+    let mut messages = Vec::new();
+    let mut visited = HashSet::new();
+    let mut node = conversation["current_node"].as_str();
+    while let Some(id) = node {
+        if !visited.insert(id) {
+            anyhow::bail!("Message {} is its own ancestor", id);
+        }
+        let entry = &mapping[id];
+        let message = &entry["message"];
+        let role = message["author"]["role"].as_str();
+        let text: Vec<&str> = message["content"]["parts"]
+            .as_array()
+            .map(|parts| parts.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let text = text.join("\n").trim().to_string();
+        if matches!(role, Some("user" | "assistant")) && !text.is_empty() {
+            messages.push(Message {
+                user: role == Some("user"),
+                text,
+                time: message["create_time"]
+                    .as_f64()
+                    .and_then(|secs| DateTime::from_timestamp(secs as i64, 0)),
+            });
+        }
+        node = entry["parent"].as_str();
+    }
+    messages.reverse();
+    Ok(("chatgpt", url, messages))
+}
+
+fn claude(conversation: &Value) -> (&'static str, String, Vec<Message>) {
+    let url = format!(
+        "https://claude.ai/chat/{}",
+        conversation["uuid"].as_str().unwrap_or_default()
+    );
+    let messages = conversation["chat_messages"]
+        .as_array()
+        .map(|messages| {
+            messages
+                .iter()
+                .filter_map(|message| {
+                    let text = match message["text"].as_str().filter(|t| !t.is_empty()) {
+                        Some(text) => text.to_string(),
+                        None => message["content"]
+                            .as_array()?
+                            .iter()
+                            .filter_map(|part| part["text"].as_str())
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    };
+                    let text = text.trim().to_string();
+                    (!text.is_empty()).then(|| Message {
+                        user: message["sender"].as_str() == Some("human"),
+                        text,
+                        time: message["created_at"]
+                            .as_str()
+                            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                            .map(|time| time.with_timezone(&Utc)),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    ("claude", url, messages)
+}
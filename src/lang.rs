@@ -0,0 +1,67 @@
+const LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "Arabic"),
+    ("cs", "Czech"),
+    ("da", "Danish"),
+    ("de", "German"),
+    ("el", "Greek"),
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fi", "Finnish"),
+    ("fr", "French"),
+    ("he", "Hebrew"),
+    ("hi", "Hindi"),
+    ("hu", "Hungarian"),
+    ("id", "Indonesian"),
+    ("it", "Italian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("nb", "Norwegian"),
+    ("nl", "Dutch"),
+    ("pl", "Polish"),
+    ("pt", "Portuguese"),
+    ("ro", "Romanian"),
+    ("ru", "Russian"),
+    ("sv", "Swedish"),
+    ("th", "Thai"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+    ("vi", "Vietnamese"),
+    ("zh", "Chinese"),
+];
+
+pub struct Language {
+    /// ISO 639-1 code, if the language is a known one
+    pub code: Option<String>,
+    pub name: String,
+}
+
+/// Resolves a language code such as "de" or "pt-BR", or a name such as "German".
+pub fn resolve(lang: &str) -> Language {
+    let lang = lang.trim();
+    let base = lang.split(['-', '_']).next().unwrap_or(lang).to_lowercase();
+    let base = if base == "no" { "nb".to_string() } else { base };
+
+    if let Some((code, name)) = LANGUAGES.iter().find(|(code, _)| *code == base) {
+        return Language {
+            code: Some(code.to_string()),
+            name: name.to_string(),
+        };
+    }
+    match LANGUAGES
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(lang))
+    {
+        Some((code, name)) => Language {
+            code: Some(code.to_string()),
+            name: name.to_string(),
+        },
+        None => Language {
+            code: None,
+            name: lang.to_string(),
+        },
+    }
+}
+
+pub fn instruction(language: &Language) -> String {
+    format!("Answer in {}.", language.name)
+}
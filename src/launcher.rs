@@ -0,0 +1,64 @@
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use crate::context::format_size;
+use crate::provider::Provider;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LauncherFormat {
+    /// Raycast list item with open and copy actions
+    Raycast,
+    /// Alfred script filter items
+    Alfred,
+}
+
+/// Describes a composed query for a launcher to preview before dispatching it.
+pub fn render(format: LauncherFormat, provider: &dyn Provider, prompt: &str, query: &str) -> Value {
+    let url = provider.query_url(query);
+    let fits = url.len() <= provider.max_url_length();
+    let title = if prompt.is_empty() {
+        format!("Ask {}", provider.name())
+    } else {
+        prompt.to_string()
+    };
+    let subtitle = format!(
+        "{} · {} prompt{}",
+        provider.name(),
+        format_size(query.len()),
+        if fits {
+            ""
+        } else {
+            " · too long for a URL, opens via clipboard"
+        }
+    );
+    let open = if fits { url.as_str() } else { provider.home() };
+
+    match format {
+        LauncherFormat::Raycast => json!({
+            "title": title,
+            "subtitle": subtitle,
+            "provider": provider.name(),
+            "url": url,
+            "prompt": query,
+            "actions": [
+                { "type": "open", "title": format!("Open in {}", provider.name()), "url": open },
+                { "type": "copy", "title": "Copy Prompt", "content": query },
+                { "type": "copy", "title": "Copy URL", "content": url },
+            ],
+        }),
+        LauncherFormat::Alfred => json!({
+            "items": [{
+                "uid": format!("search-{}", provider.name()),
+                "title": title,
+                "subtitle": subtitle,
+                "arg": open,
+                "text": { "copy": query, "largetype": query },
+                "quicklookurl": open,
+                "variables": { "provider": provider.name(), "fits_url": fits },
+                "mods": {
+                    "cmd": { "arg": query, "subtitle": "Copy the composed prompt" },
+                },
+            }],
+        }),
+    }
+}
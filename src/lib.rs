@@ -0,0 +1,62 @@
+//! Composes prompts from files, pasted text, and templates, and sends them to LLM providers.
+
+pub mod batch;
+pub mod browser;
+pub mod clipboard;
+pub mod config;
+pub mod context;
+#[cfg(feature = "daemon")]
+pub mod daemon;
+pub mod deps;
+pub mod desktop;
+pub mod dictate;
+pub mod dispatch;
+pub mod editor;
+pub mod env;
+pub mod error;
+pub mod explain;
+pub mod format;
+pub mod ground;
+pub mod history;
+pub mod hook;
+pub mod import;
+pub mod lang;
+pub mod launcher;
+pub mod library;
+pub mod lsp;
+pub mod manifest;
+pub mod mcp;
+pub mod minify;
+pub mod native;
+pub mod ops;
+pub mod output;
+pub mod paste;
+pub mod pipeline;
+pub mod popup;
+pub mod provider;
+pub mod rank;
+pub mod redact;
+pub mod repo;
+pub mod review;
+pub mod ring;
+pub mod sanitize;
+pub mod scheme;
+pub mod serve;
+pub mod snip;
+pub mod source;
+pub mod stats;
+pub mod summarize;
+pub mod sysinfo;
+pub mod tail;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod webdriver;
+pub mod wizard;
+pub mod wsl;
+
+pub use dispatch::Dispatcher;
+pub use error::SearchError;
+pub use format::Formatter;
+pub use pipeline::{compose, ComposeOptions, Composed};
+pub use provider::Provider;
+pub use source::ContextSource;
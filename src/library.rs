@@ -0,0 +1,126 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+use crate::config::{self, LibraryConfig};
+
+pub fn library_dir(config: &LibraryConfig) -> Option<PathBuf> {
+    config
+        .path
+        .clone()
+        .or_else(|| config::data_dir().map(|dir| dir.join("library")))
+}
+
+pub fn sync(config: &LibraryConfig) -> Result<PathBuf> {
+    let repo = config
+        .repo
+        .as_deref()
+        .context("No template library configured; set [library] repo in the config file")?;
+    let dir =
+        library_dir(config).context("Could not determine a data directory for the library")?;
+
+    let mut cmd = Command::new("git");
+    if dir.join(".git").exists() {
+        cmd.arg("-C").arg(&dir).args(["pull", "--ff-only"]);
+    } else {
+        if let Some(parent) = dir.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        cmd.args(["clone", "--depth", "1"]);
+        if let Some(branch) = &config.branch {
+            cmd.arg("--branch").arg(branch);
+        }
+        cmd.arg(repo).arg(&dir);
+    }
+
+    let status = cmd.status().context("Failed to run git")?;
+    if !status.success() {
+        anyhow::bail!("git failed to sync the template library from {}", repo);
+    }
+    Ok(dir)
+}
+
+/// Template and bundle names available in the library.
+pub fn list(config: &LibraryConfig) -> Result<(Vec<String>, Vec<String>)> {
+    let Some(dir) = library_dir(config) else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+    Ok((names(&dir.join("templates"))?, names(&dir.join("bundles"))?))
+}
+
+fn files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn stem(path: &Path) -> Option<&str> {
+    path.file_stem().and_then(|s| s.to_str())
+}
+
+fn names(dir: &Path) -> Result<Vec<String>> {
+    Ok(files(dir)?
+        .iter()
+        .filter_map(|path| stem(path).map(str::to_string))
+        .collect())
+}
+
+fn find(config: &LibraryConfig, kind: &str, name: &str) -> Result<PathBuf> {
+    let dir = library_dir(config)
+        .context("Could not determine a data directory for the library")?
+        .join(kind);
+    files(&dir)?
+        .into_iter()
+        .find(|path| {
+            path.file_name().and_then(|s| s.to_str()) == Some(name) || stem(path) == Some(name)
+        })
+        .with_context(|| {
+            format!(
+                "No {} named {:?} in {}; run `search template sync` first?",
+                kind.trim_end_matches('s'),
+                name,
+                dir.display()
+            )
+        })
+}
+
+/// Applies a library template to the prompt, substituting `{prompt}` or appending it.
+pub fn apply_template(config: &LibraryConfig, name: &str, prompt: &str) -> Result<String> {
+    let path = find(config, "templates", name)?;
+    let template = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read template {}", path.display()))?;
+    let template = template.trim_end();
+    if template.contains("{prompt}") {
+        Ok(template.replace("{prompt}", prompt))
+    } else if prompt.is_empty() {
+        Ok(template.to_string())
+    } else {
+        Ok(format!("{}\n\n{}", template, prompt))
+    }
+}
+
+/// Context paths listed in a bundle, one per line, `#` starting a comment.
+pub fn bundle(config: &LibraryConfig, name: &str) -> Result<Vec<String>> {
+    let path = find(config, "bundles", name)?;
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read bundle {}", path.display()))?;
+    Ok(text
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
@@ -0,0 +1,481 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::context::{self, ContextFile};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const READY_TIMEOUT: Duration = Duration::from_secs(120);
+const MAX_SYMBOLS: usize = 150;
+const MAX_DEFINITION_LINES: usize = 200;
+
+pub fn default_server(lang: &str) -> Option<&'static str> {
+    match lang {
+        "rust" => Some("rust-analyzer"),
+        "python" => Some("pylsp"),
+        "typescript" | "tsx" | "javascript" | "jsx" => Some("typescript-language-server --stdio"),
+        "go" => Some("gopls"),
+        "c" | "cpp" => Some("clangd"),
+        _ => None,
+    }
+}
+
+struct LspClient {
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+    next_id: i64,
+    quiescent: bool,
+}
+
+impl LspClient {
+    fn start(command: &str, root: &Path) -> Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().context("Empty language server command")?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("Failed to start language server `{}`", command))?;
+
+        let stdin = child.stdin.take().context("Language server has no stdin")?;
+        let stdout = child
+            .stdout
+            .take()
+            .context("Language server has no stdout")?;
+        let (tx, messages) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            while let Some(message) = read_message(&mut reader) {
+                if tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut client = Self {
+            child,
+            stdin,
+            messages,
+            next_id: 0,
+            quiescent: false,
+        };
+
+        let root_uri = Url::from_directory_path(root)
+            .map_err(|_| anyhow::anyhow!("Invalid workspace root {}", root.display()))?;
+        client.request(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": root_uri.as_str(),
+                "workspaceFolders": [{ "uri": root_uri.as_str(), "name": "search" }],
+                "capabilities": {
+                    "textDocument": {
+                        "definition": { "linkSupport": true },
+                        "documentSymbol": { "hierarchicalDocumentSymbolSupport": true },
+                    },
+                    "experimental": { "serverStatusNotification": true },
+                },
+                "initializationOptions": {
+                    "checkOnSave": false,
+                    "cachePriming": { "enable": false },
+                },
+            }),
+        )?;
+        client.notify("initialized", json!({}))?;
+
+        Ok(client)
+    }
+
+    fn send(&mut self, message: Value) -> Result<()> {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body)
+            .and_then(|_| self.stdin.flush())
+            .context("Failed to write to language server")
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+        loop {
+            let Some(message) = self.next_message(deadline)? else {
+                continue;
+            };
+            if message.get("id") == Some(&json!(id)) {
+                if let Some(error) = message.get("error") {
+                    anyhow::bail!("Language server {} request failed: {}", method, error);
+                }
+                return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+            }
+        }
+    }
+
+    fn next_message(&mut self, deadline: Instant) -> Result<Option<Value>> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let message = self
+            .messages
+            .recv_timeout(remaining)
+            .context("Timed out waiting for language server")?;
+
+        match (
+            message.get("method").and_then(|m| m.as_str()),
+            message.get("id"),
+        ) {
+            (Some(method), Some(id)) => {
+                let result = if method == "workspace/configuration" {
+                    let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+                    Value::Array(vec![Value::Null; items])
+                } else {
+                    Value::Null
+                };
+                let id = id.clone();
+                self.send(json!({ "jsonrpc": "2.0", "id": id, "result": result }))?;
+                Ok(None)
+            }
+            (Some("experimental/serverStatus"), None) => {
+                self.quiescent = message["params"]["quiescent"].as_bool().unwrap_or(false);
+                Ok(None)
+            }
+            (Some(_), None) => Ok(None),
+            (None, _) => Ok(Some(message)),
+        }
+    }
+
+    fn wait_until_ready(&mut self) {
+        let deadline = Instant::now() + READY_TIMEOUT;
+        while !self.quiescent {
+            if self.next_message(deadline).is_err() {
+                break;
+            }
+        }
+    }
+
+    fn shutdown(mut self) {
+        let _ = self.request("shutdown", Value::Null);
+        let _ = self.notify("exit", Value::Null);
+        let _ = self.child.wait();
+    }
+}
+
+fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut body = vec![0; length?];
+    reader.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+struct Location {
+    path: PathBuf,
+    start: usize,
+    end: Option<usize>,
+}
+
+fn parse_locations(result: &Value) -> Vec<Location> {
+    let items = match result {
+        Value::Array(items) => items.clone(),
+        Value::Object(_) => vec![result.clone()],
+        _ => Vec::new(),
+    };
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let (uri, range, full) = match item.get("targetUri") {
+                Some(uri) => (
+                    uri,
+                    &item["targetSelectionRange"],
+                    Some(&item["targetRange"]),
+                ),
+                None => (item.get("uri")?, &item["range"], None),
+            };
+            let path = Url::parse(uri.as_str()?).ok()?.to_file_path().ok()?;
+            Some(Location {
+                path,
+                start: full.unwrap_or(range)["start"]["line"].as_u64()? as usize,
+                end: full
+                    .and_then(|r| r["end"]["line"].as_u64())
+                    .map(|l| l as usize),
+            })
+        })
+        .collect()
+}
+
+fn symbol_range(symbols: &Value, line: usize) -> Option<(usize, usize)> {
+    symbols.as_array()?.iter().find_map(|symbol| {
+        let range = symbol
+            .get("range")
+            .or_else(|| symbol["location"].get("range"))?;
+        let start = range["start"]["line"].as_u64()? as usize;
+        let end = range["end"]["line"].as_u64()? as usize;
+        if !(start <= line && line <= end) {
+            return None;
+        }
+        symbol
+            .get("children")
+            .and_then(|children| symbol_range(children, line))
+            .or(Some((start, end)))
+    })
+}
+
+fn symbols(content: &str) -> Vec<(String, usize, usize)> {
+    let ident = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*(\s*[(!]|::)?").unwrap();
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+
+    for (line_no, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("//") || trimmed.starts_with('#') {
+            continue;
+        }
+        for cap in ident.captures_iter(line) {
+            let whole = cap.get(0).unwrap();
+            let name = whole
+                .as_str()
+                .trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_');
+            let is_call = cap.get(1).is_some();
+            let is_type = name.starts_with(|c: char| c.is_ascii_uppercase());
+            if !(is_call || is_type) || is_keyword(name) || !seen.insert(name.to_string()) {
+                continue;
+            }
+            let character = line[..whole.start()].encode_utf16().count();
+            found.push((name.to_string(), line_no, character));
+            if found.len() >= MAX_SYMBOLS {
+                return found;
+            }
+        }
+    }
+
+    found
+}
+
+fn is_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "if" | "else"
+            | "for"
+            | "while"
+            | "loop"
+            | "match"
+            | "return"
+            | "fn"
+            | "let"
+            | "mut"
+            | "pub"
+            | "use"
+            | "mod"
+            | "struct"
+            | "enum"
+            | "impl"
+            | "trait"
+            | "where"
+            | "as"
+            | "in"
+            | "self"
+            | "Self"
+            | "super"
+            | "crate"
+            | "def"
+            | "class"
+            | "import"
+            | "from"
+            | "function"
+            | "const"
+            | "var"
+            | "new"
+            | "typeof"
+            | "print"
+            | "Some"
+            | "None"
+            | "Ok"
+            | "Err"
+            | "True"
+            | "False"
+            | "switch"
+            | "catch"
+            | "func"
+    )
+}
+
+pub fn definitions(
+    files: &[ContextFile],
+    servers: &HashMap<String, String>,
+) -> Result<Vec<ContextFile>> {
+    let root = std::env::current_dir().context("Failed to get current directory")?;
+    let included: HashSet<PathBuf> = files
+        .iter()
+        .filter_map(|f| std::fs::canonicalize(&f.path).ok())
+        .collect();
+
+    let mut by_server: HashMap<String, Vec<&ContextFile>> = HashMap::new();
+    for file in files {
+        let Some(lang) = file.lang else { continue };
+        let server = servers
+            .get(lang)
+            .cloned()
+            .or_else(|| default_server(lang).map(str::to_string));
+        if let Some(server) = server {
+            by_server.entry(server).or_default().push(file);
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    for (server, files) in by_server {
+        let mut client = LspClient::start(&server, &root)?;
+
+        for file in &files {
+            let path = std::fs::canonicalize(&file.path)
+                .with_context(|| format!("Failed to resolve {}", file.path.display()))?;
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let uri = Url::from_file_path(&path)
+                .map_err(|_| anyhow::anyhow!("Invalid path {}", path.display()))?;
+            client.notify(
+                "textDocument/didOpen",
+                json!({
+                    "textDocument": {
+                        "uri": uri.as_str(),
+                        "languageId": file.lang.unwrap_or("plaintext"),
+                        "version": 1,
+                        "text": text,
+                    }
+                }),
+            )?;
+        }
+        if server
+            .split_whitespace()
+            .next()
+            .is_some_and(|p| p.ends_with("rust-analyzer"))
+        {
+            client.wait_until_ready();
+        }
+
+        let mut symbol_cache: HashMap<PathBuf, Value> = HashMap::new();
+        for file in &files {
+            let path = std::fs::canonicalize(&file.path)?;
+            let uri = Url::from_file_path(&path)
+                .map_err(|_| anyhow::anyhow!("Invalid path {}", path.display()))?;
+
+            for (name, line, character) in symbols(&file.content) {
+                let Ok(result) = client.request(
+                    "textDocument/definition",
+                    json!({
+                        "textDocument": { "uri": uri.as_str() },
+                        "position": { "line": line, "character": character },
+                    }),
+                ) else {
+                    continue;
+                };
+
+                for location in parse_locations(&result) {
+                    if !location.path.starts_with(&root) || included.contains(&location.path) {
+                        continue;
+                    }
+                    let end = match location.end {
+                        Some(end) => end,
+                        None => {
+                            if !symbol_cache.contains_key(&location.path) {
+                                let target = Url::from_file_path(&location.path)
+                                    .map_err(|_| anyhow::anyhow!("Invalid path"))?;
+                                let symbols = client
+                                    .request(
+                                        "textDocument/documentSymbol",
+                                        json!({ "textDocument": { "uri": target.as_str() } }),
+                                    )
+                                    .unwrap_or(Value::Null);
+                                symbol_cache.insert(location.path.clone(), symbols);
+                            }
+                            symbol_range(&symbol_cache[&location.path], location.start)
+                                .map_or(location.start, |(_, end)| end)
+                        }
+                    };
+                    if !seen.insert((location.path.clone(), location.start)) {
+                        continue;
+                    }
+                    if let Some(definition) =
+                        excerpt(&location.path, &root, &name, location.start, end)?
+                    {
+                        found.push(definition);
+                    }
+                }
+            }
+        }
+
+        client.shutdown();
+    }
+
+    Ok(found)
+}
+
+fn excerpt(
+    path: &Path,
+    root: &Path,
+    name: &str,
+    start: usize,
+    end: usize,
+) -> Result<Option<ContextFile>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let lines: Vec<&str> = text.lines().collect();
+    if start >= lines.len() {
+        return Ok(None);
+    }
+
+    let end = end.min(lines.len() - 1);
+    if start == 0 && end == lines.len() - 1 {
+        return Ok(None);
+    }
+    let truncated = end - start + 1 > MAX_DEFINITION_LINES;
+    let last = if truncated {
+        start + MAX_DEFINITION_LINES - 1
+    } else {
+        end
+    };
+    let content = lines[start..=last].join("\n");
+
+    Ok(Some(ContextFile {
+        path: context::relative_path(path, root),
+        lang: context::detect_language(path, &text),
+        size: lines[start..=end].iter().map(|l| l.len() + 1).sum(),
+        content,
+        truncated,
+        note: Some(format!(
+            "definition of `{}`, lines {}-{}",
+            name,
+            start + 1,
+            end + 1
+        )),
+    }))
+}
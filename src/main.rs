@@ -1,10 +1,32 @@
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Once;
+use std::time::Duration;
+
 use anyhow::{Context, Result};
-use arboard::Clipboard;
-use clap::{command, Parser};
-use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
-use webbrowser;
+use chrono::{Local, NaiveDate, NaiveTime, Utc};
+use clap::{Parser, Subcommand};
+
+use search::browser::{BrowserOptions, SystemBrowser};
+use search::clipboard::{Clipboard, SystemClipboard};
+use search::config::{Config, ConfirmConfig};
+use search::context::{estimate_tokens, format_size};
+#[cfg(feature = "daemon")]
+use search::daemon;
+use search::dispatch::{DesktopDispatcher, Dispatcher, UrlDispatcher, WebDriverDispatcher};
+use search::error::SearchError;
+use search::format::{Order, Paste};
+use search::output::{self, ColorChoice};
+use search::pipeline::{self, ComposeOptions, Composed};
+use search::provider::{Provider, Style, UrlProvider};
+use search::source::{self, ContextSource};
+use search::{
+    batch, dictate, editor, explain, history, hook, import, launcher, library, mcp, native, paste,
+    popup, redact, review, ring, scheme, serve, snip, stats, tail, wizard, wsl,
+};
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 #[command(help_template = "{before-help}{name} {version}
 {author-with-newline}{about-with-newline}
@@ -17,105 +39,1611 @@ Examples:
     search --clipboard \"what's causing this error?\"
     search -x main.py \"walk me through this code\"
     search -p chatgpt \"how do I write a fish function\"
+    search history list race condition
 ")]
 struct Args {
-    /// Invoke conscious-data/contextualize to load content from specified paths
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Re-send the previously composed prompt, e.g. to another provider with -p
+    #[arg(long, conflicts_with_all = ["context", "bundle", "clipboard", "deps", "manifest", "template"])]
+    last: bool,
+
+    /// Load the context paths listed in a library bundle
+    #[arg(long, value_name = "NAME", conflicts_with = "clipboard")]
+    bundle: Option<String>,
+
+    /// Load content from specified files and directories
     #[arg(short = 'x', long, num_args = 1.., value_delimiter = ' ')]
     context: Option<Vec<String>>,
 
-    /// Inject clipboard content as context
-    #[arg(short, long)]
-    clipboard: bool,
+    /// Invoke conscious-data/contextualize to load --context paths instead of reading them directly
+    #[arg(long, requires = "context")]
+    contextualize: bool,
+
+    /// Truncate context files larger than this many bytes
+    #[arg(long, default_value_t = 100_000)]
+    max_file_bytes: usize,
+
+    /// Include a file along with the local modules it imports
+    #[arg(long, value_name = "FILE")]
+    deps: Vec<String>,
+
+    /// Append definitions of symbols used in context files, resolved via a language server
+    #[arg(long)]
+    definitions: bool,
+
+    /// Maximum total bytes of file context, filled with the files most relevant to the prompt first
+    #[arg(long)]
+    budget: Option<usize>,
+
+    /// Summarize trailing context files with a local model until they fit the budget
+    #[arg(long, requires = "budget")]
+    summarize: bool,
+
+    /// Include the project manifest and a lockfile summary as context
+    #[arg(long)]
+    manifest: bool,
+
+    /// Collapse blank lines, trim trailing spaces, and strip license headers from context files
+    #[arg(long)]
+    minify: bool,
+
+    /// Prefix context file lines with line numbers
+    #[arg(short = 'n', long)]
+    line_numbers: bool,
+
+    /// Inject clipboard content as context, or with --clipboard=N an earlier capture
+    #[arg(
+        short,
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1"
+    )]
+    clipboard: Option<usize>,
+
+    /// Wrap the prompt in a template from the shared library
+    #[arg(short = 't', long, value_name = "NAME")]
+    template: Option<String>,
 
     /// LLM provider to use
     #[arg(short, long, default_value = "chatgpt")]
     provider: String,
 
+    /// Continue an existing conversation, given by its id or URL, instead of starting a new chat
+    #[arg(long, value_name = "ID|URL", conflicts_with = "desktop")]
+    chat: Option<String>,
+
+    /// Context formatting style, instead of the provider's default
+    #[arg(long, value_enum)]
+    style: Option<Style>,
+
+    /// Put the prompt before or after the context
+    #[arg(long, value_enum)]
+    order: Option<Order>,
+
+    /// Order of context sections by source, e.g. diff,files,clipboard
+    #[arg(long, value_delimiter = ',', value_name = "SOURCES")]
+    source_order: Vec<String>,
+
+    /// Ask for the answer in this language, e.g. de or German
+    #[arg(long, value_name = "LANG")]
+    lang: Option<String>,
+
+    /// Prefer search results from this country, e.g. de or us
+    #[arg(long, value_name = "COUNTRY")]
+    region: Option<String>,
+
+    /// Search engine language and country, e.g. de-DE
+    #[arg(long, value_name = "LOCALE")]
+    locale: Option<String>,
+
+    /// Print the provider URL to stdout
+    #[arg(long, global = true)]
+    print_url: bool,
+
+    /// Copy the provider URL to the clipboard
+    #[arg(long, global = true)]
+    copy_url: bool,
+
+    /// Render the provider URL as a QR code in the terminal
+    #[arg(long, global = true)]
+    qr: bool,
+
+    /// Don't open the provider URL in a browser
+    #[arg(long, global = true)]
+    no_open: bool,
+
+    /// Open the browser even when stdout is piped, instead of printing the URL
+    #[arg(long, global = true, conflicts_with = "no_open")]
+    open: bool,
+
+    /// Browser to open, by name (chrome, firefox, ...) or executable path
+    #[arg(long, global = true, value_name = "NAME|PATH")]
+    browser: Option<String>,
+
+    /// Browser profile to open the provider in
+    #[arg(long, global = true, value_name = "NAME")]
+    browser_profile: Option<String>,
+
+    /// Open the provider in a private window
+    #[arg(long, global = true)]
+    incognito: bool,
+
+    /// Type the prompt into the provider's chat box through a WebDriver server instead of a URL
+    #[arg(long, conflicts_with_all = ["print_url", "copy_url", "qr", "no_open"])]
+    webdriver: bool,
+
+    /// Paste the prompt into the provider's macOS desktop app instead of a browser
+    #[arg(long, conflicts_with_all = ["webdriver", "print_url", "copy_url", "qr", "no_open"])]
+    desktop: bool,
+
+    /// Press return after pasting into the desktop app
+    #[arg(long, requires = "desktop")]
+    submit: bool,
+
+    /// Print a launcher preview of the query as JSON instead of sending it
+    #[arg(long, value_enum, value_name = "LAUNCHER")]
+    format: Option<launcher::LauncherFormat>,
+
+    /// Also write the composed prompt to a file
+    #[arg(long, value_name = "PATH")]
+    tee: Option<PathBuf>,
+
+    /// Send the prompt even if the same one was sent recently
+    #[arg(long)]
+    force: bool,
+
+    /// Send the prompt as-is, without stripping escape codes or normalizing Unicode
+    #[arg(long)]
+    raw: bool,
+
+    /// Don't mask secrets such as API keys and private keys in the prompt
+    #[arg(long)]
+    no_redact: bool,
+
+    /// Include the end of a log file as context
+    #[arg(long, value_name = "FILE")]
+    tail: Vec<PathBuf>,
+
+    /// Lines to take from the end of each --tail file and --docker-logs container
+    #[arg(long, value_name = "N", default_value_t = 200)]
+    tail_lines: usize,
+
+    /// Take --tail and --docker-logs entries from within this long ago instead, e.g. 10m or 2h
+    #[arg(long, value_name = "DURATION", value_parser = tail::parse_duration)]
+    since: Option<Duration>,
+
+    /// Include OS, shell, and tool versions, and the names of environment variables matching
+    /// --env=REGEX, or all of them
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ""
+    )]
+    env: Option<String>,
+
+    /// Show the values of --env variables, except ones named like secrets
+    #[arg(long, requires = "env")]
+    env_values: bool,
+
+    /// Include the OS, kernel, CPU, memory, and toolchain versions as context
+    #[arg(long)]
+    sysinfo: bool,
+
+    /// Include the recent logs of a Docker container as context
+    #[arg(long, value_name = "CONTAINER")]
+    docker_logs: Vec<String>,
+
+    /// Include the output of a kubectl command as context, e.g. "logs deploy/api --since=10m"
+    #[arg(long, value_name = "COMMAND")]
+    kubectl: Vec<String>,
+
+    /// Search the web for the prompt first and include the top results as cited context
+    #[arg(long)]
+    ground: bool,
+
+    /// Upload a prompt too long for the URL to the configured paste service and send the link
+    #[arg(long)]
+    paste_overflow: bool,
+
+    /// Send large prompts without asking, despite the [confirm] limits
+    #[arg(short, long)]
+    yes: bool,
+
+    /// Speak the prompt: record until Enter and transcribe it with the [dictate] commands
+    #[arg(long)]
+    dictate: bool,
+
+    /// Leave out the git repository, branch, and commit that otherwise start the context
+    #[arg(long)]
+    no_repo_header: bool,
+
+    /// Show a diff of what redaction changed and ask before sending
+    #[arg(long, conflicts_with = "no_redact")]
+    confirm_redactions: bool,
+
+    /// When to color messages and tables; NO_COLOR turns off "auto"
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Report how long slow stages took
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Don't record this query in the history
+    #[arg(long, global = true)]
+    no_history: bool,
+
+    /// Group this query under a named session, e.g. a ticket id
+    #[arg(long, global = true, value_name = "NAME")]
+    session: Option<String>,
+
+    /// Copy the --session's queries into a new session and continue there
+    #[arg(long, global = true, value_name = "NAME", requires = "session")]
+    fork: Option<String>,
+
     /// prompt/query text
     #[arg(trailing_var_arg = true)]
     prompt: Vec<String>,
 }
-fn get_clipboard_content() -> Result<String> {
-    let mut clipboard = Clipboard::new().context("Failed to initialize clipboard")?;
-    clipboard
-        .get_text()
-        .context("Failed to get clipboard content")
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Send each prompt in a file, one per line or as YAML entries with provider and context
+    Batch {
+        file: PathBuf,
+
+        /// Directory for per-query transcripts
+        #[arg(short, long, value_name = "DIR")]
+        output: Option<PathBuf>,
+
+        /// Milliseconds to wait between opening tabs
+        #[arg(long, value_name = "MS")]
+        delay: Option<u64>,
+
+        /// Tabs to open before asking to continue
+        #[arg(long, value_name = "N")]
+        max_tabs: Option<usize>,
+    },
+    /// Explain a shell command, using its --help output and man page as context
+    Explain {
+        /// Ask for the invocation that does this instead, e.g. "extract into /tmp"
+        #[arg(long, value_name = "TASK")]
+        how: Option<String>,
+
+        /// The command, after --
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Import conversations from a ChatGPT or Claude data export into the history
+    Import {
+        /// The export zip, or the conversations.json inside it
+        file: PathBuf,
+    },
+    /// Browse, inspect, and re-run past queries
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+    },
+    /// Compose a query about the current tmux pane's output in a popup
+    Popup {
+        #[arg(long, hide = true)]
+        capture: Option<PathBuf>,
+    },
+    /// Ask for a code review of the staged changes, a branch, or a pull request
+    Review {
+        /// Review a GitHub pull request by number or URL, fetched with gh
+        #[arg(long, value_name = "PR", conflicts_with = "base")]
+        pr: Option<String>,
+
+        /// Review the commits since this ref instead of the staged changes
+        #[arg(long, value_name = "REF")]
+        base: Option<String>,
+
+        /// Extra instructions for the reviewer
+        #[arg(trailing_var_arg = true)]
+        prompt: Vec<String>,
+    },
+    /// Manage custom providers
+    Providers {
+        #[command(subcommand)]
+        action: ProvidersAction,
+    },
+    /// Check the secret redaction rules
+    Redact {
+        #[command(subcommand)]
+        action: RedactAction,
+    },
+    /// Save useful commands and code blocks, and copy them back out later
+    Snip {
+        #[command(subcommand)]
+        action: SnipAction,
+    },
+    /// Manage the shared template library
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// Compose a query interactively: edit the prompt, pick files and a provider, and preview it
+    #[cfg(feature = "tui")]
+    Tui,
+    /// Serve the compose and search pipeline as Model Context Protocol tools over stdio
+    Mcp,
+    /// Wait for a global hotkey, then ask for a prompt about the current selection
+    #[cfg(feature = "daemon")]
+    Daemon {
+        /// Also send copied text that starts with this prefix, e.g. "??"
+        #[arg(long, value_name = "PREFIX")]
+        trigger: Option<String>,
+    },
+    /// Run as a browser extension's native messaging host
+    NativeHost {
+        #[command(subcommand)]
+        action: Option<NativeAction>,
+
+        /// Arguments the browser passes when launching the host
+        #[arg(hide = true)]
+        caller: Vec<String>,
+    },
+    /// Run a query from a search://query?p=...&q=... link
+    HandleUrl {
+        #[arg(required_unless_present = "register")]
+        url: Option<String>,
+
+        /// Register this executable as the system handler for search:// links
+        #[arg(long, conflicts_with = "url")]
+        register: bool,
+    },
+    /// Serve a local HTTP API for composing and dispatching queries
+    Serve {
+        #[arg(long, default_value_t = 8377)]
+        port: u16,
+    },
+    /// List recent clipboard captures, to pick one with --clipboard=N
+    Clips,
+    /// Summarize queries per day and per provider from the history
+    Stats {
+        /// Number of days to include, counting today
+        #[arg(long, default_value_t = 14)]
+        days: u32,
+    },
 }
 
-fn format_content(content: &str, query: &[String]) -> String {
-    let formatted = if content.contains("```") {
-        format!("<paste>\n{}\n</paste>", content)
-    } else {
-        format!("```paste\n{}\n```", content)
-    };
+#[derive(Subcommand, Debug, Clone)]
+enum NativeAction {
+    /// Register the host with a browser for the companion extension
+    Install {
+        #[arg(long, value_enum)]
+        browser: native::Browser,
 
-    if !query.is_empty() {
-        format!("{}\n{}", formatted, query.join(" "))
-    } else {
-        formatted
+        /// Extension id allowed to talk to the host
+        #[arg(long)]
+        extension_id: String,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ProvidersAction {
+    /// Interactively add a provider to the config file
+    Add,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum RedactAction {
+    /// Show what redaction would replace in a file, and what the allowlist lets through
+    Test { file: PathBuf },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum SnipAction {
+    /// Save a snippet from a file, stdin, a history answer, or else the clipboard
+    Save {
+        name: String,
+
+        /// Tag to file the snippet under; repeatable
+        #[arg(short, long)]
+        tag: Vec<String>,
+
+        /// Read the snippet from this file
+        #[arg(long, conflicts_with = "from")]
+        file: Option<PathBuf>,
+
+        /// Take the answer of this history entry
+        #[arg(long, value_name = "ID")]
+        from: Option<u64>,
+
+        /// Keep only the Nth fenced code block, counting from 1
+        #[arg(long, value_name = "N")]
+        block: Option<usize>,
+    },
+    /// List saved snippets
+    List {
+        /// Only show snippets with this tag
+        #[arg(short, long)]
+        tag: Option<String>,
+    },
+    /// Copy a snippet to the clipboard
+    Copy { name: String },
+    /// Print a snippet to stdout
+    Show { name: String },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum TemplateAction {
+    /// Clone or pull the configured library repository
+    Sync,
+    /// List the templates and context bundles in the library
+    List,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum HistoryAction {
+    /// List past queries, optionally fuzzy-filtered
+    List {
+        /// Text to fuzzy-match against prompts and context sources
+        query: Vec<String>,
+
+        /// Maximum number of entries to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// List named sessions and their query counts
+    Sessions,
+    /// Show the full composed prompt of a past query
+    Show { id: u64 },
+    /// Send a past query's composed prompt again
+    Rerun {
+        id: u64,
+
+        /// Send to this provider instead of the original one
+        #[arg(short, long)]
+        provider: Option<String>,
+    },
+    /// Write all entries to stdout for archiving or other tools
+    Export {
+        #[arg(long, value_enum, default_value_t = history::ExportFormat::Jsonl)]
+        format: history::ExportFormat,
+    },
+    /// Delete entries recorded before a date
+    Purge {
+        /// Date in YYYY-MM-DD form, in local time
+        #[arg(long, value_name = "DATE")]
+        before: String,
+    },
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    output::init(args.color, args.verbose);
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            output::error(format!("{:?}", e));
+            let code = e
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<SearchError>())
+                .map_or(1, SearchError::exit_code);
+            ExitCode::from(code)
+        }
     }
 }
 
-fn get_provider_url(provider: &str, query: &str) -> Result<String> {
-    let encoded_query = utf8_percent_encode(query, NON_ALPHANUMERIC).to_string();
+fn run(mut args: Args) -> Result<()> {
+    let config = Config::load()?;
 
-    let url = match provider {
-        "claude" => format!("https://claude.ai/new?q={}", encoded_query),
-        "chatgpt" => format!("https://chatgpt.com/?q={}", encoded_query),
-        _ => anyhow::bail!("Unsupported provider: {}", provider),
+    if let (Some(from), Some(to)) = (&args.session, args.fork.take()) {
+        let copied = history::fork(&config.history, from, &to)?;
+        output::note(format!(
+            "Forked session {} into {} ({} queries)",
+            from, to, copied
+        ));
+        args.session = Some(to);
+        if args.command.is_none() && args.prompt.is_empty() && !args.last {
+            return Ok(());
+        }
+    }
+
+    if args.dictate {
+        let text = dictate::prompt(&config.dictate)?;
+        if text.is_empty() {
+            anyhow::bail!("Nothing was transcribed");
+        }
+        output::note(format!("Heard: {}", text));
+        args.prompt.push(text);
+    }
+
+    // Quoting a long prompt in the shell is painful, so ask for it instead.
+    if args.command.is_none()
+        && args.prompt.is_empty()
+        && !args.last
+        && std::io::stdin().is_terminal()
+    {
+        let prompt = editor::prompt()?;
+        if prompt.is_empty() {
+            output::note("Nothing was sent");
+            return Ok(());
+        }
+        args.prompt = vec![prompt];
+    }
+
+    match &args.command {
+        Some(Command::Batch {
+            file,
+            output,
+            delay,
+            max_tabs,
+        }) => {
+            let delay = delay.or(config.tabs.delay_ms).unwrap_or(1_000);
+            let max_tabs = max_tabs.or(config.tabs.max_tabs).unwrap_or(5);
+            let delay = Duration::from_millis(delay);
+            run_batch(
+                &args,
+                &config,
+                file,
+                output.as_deref(),
+                delay,
+                max_tabs.max(1),
+            )
+        }
+        Some(Command::Explain { how, command }) => {
+            run_explain(&args, &config, command, how.as_deref())
+        }
+        Some(Command::Import { file }) => run_import(file, &config),
+        Some(Command::History { action }) => run_history(action.as_ref(), &args, &config),
+        Some(Command::Snip { action }) => run_snip(action, &config),
+        Some(Command::Popup { capture: None }) => popup::open(&args.provider),
+        Some(Command::Popup {
+            capture: Some(capture),
+        }) => run_popup(&args, &config, capture).inspect_err(popup::pause_on_error),
+        Some(Command::Review { pr, base, prompt }) => {
+            run_review(&args, &config, pr.as_deref(), base.as_deref(), prompt)
+        }
+        Some(Command::Providers {
+            action: ProvidersAction::Add,
+        }) => {
+            let options = browser_options(Some(&args), &config);
+            let path = wizard::add_provider(&config, &SystemBrowser(&options))?;
+            output::note(format!("Saved the provider to {}", path.display()));
+            Ok(())
+        }
+        Some(Command::Redact {
+            action: RedactAction::Test { file },
+        }) => run_redact_test(file, &config),
+        #[cfg(feature = "tui")]
+        Some(Command::Tui) => run_tui(&args, &config),
+        Some(Command::Template { action }) => run_template(action, &config),
+        Some(Command::Mcp) => mcp::serve(&mcp_tools(), |name, arguments| {
+            call_mcp_tool(name, arguments, &config)
+        }),
+        #[cfg(feature = "daemon")]
+        Some(Command::Daemon { trigger }) => {
+            let mut daemon = config.daemon.clone();
+            daemon.trigger = trigger.clone().or(daemon.trigger);
+            daemon::run(&daemon, |selection, prompt| {
+                run_daemon_query(&args, &config, selection, prompt)
+            })
+        }
+        Some(Command::NativeHost { action, .. }) => match action {
+            Some(NativeAction::Install {
+                browser,
+                extension_id,
+            }) => {
+                let path = native::install(*browser, extension_id)?;
+                output::note(format!(
+                    "Installed native messaging host manifest at {}",
+                    path.display()
+                ));
+                Ok(())
+            }
+            None => native::serve(|message| handle_native_message(message, &args, &config)),
+        },
+        Some(Command::HandleUrl { url, register }) => match url {
+            Some(url) if !register => handle_url(url, &config),
+            _ => {
+                let path = scheme::register()?;
+                output::note(format!(
+                    "Registered search:// handler at {}",
+                    path.display()
+                ));
+                Ok(())
+            }
+        },
+        Some(Command::Serve { port }) => {
+            serve::serve(*port, &config.serve.allowed_origins, |input| {
+                handle_serve_query(input, &config)
+            })
+        }
+        Some(Command::Clips) => {
+            for (i, capture) in ring::load(&config.history)?.iter().enumerate() {
+                println!(
+                    "{:>2}  {}  {}",
+                    i + 1,
+                    capture.local_time(),
+                    capture.summary()
+                );
+            }
+            Ok(())
+        }
+        Some(Command::Stats { days }) => {
+            stats::print(&history::load(&config.history)?, *days);
+            Ok(())
+        }
+        None => run_query(&args, &config),
+    }
+}
+
+fn run_batch(
+    args: &Args,
+    config: &Config,
+    file: &Path,
+    output: Option<&Path>,
+    delay: Duration,
+    max_tabs: usize,
+) -> Result<()> {
+    let entries = batch::load(file)?;
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| batch::default_output_dir(file));
+    let mut dispatch = dispatch_options(args, config);
+    dispatch.browser.new_window = config.tabs.new_window;
+    let mut opened = 0;
+
+    for (i, mut entry) in entries.into_iter().enumerate() {
+        if entry["provider"].is_null() {
+            entry["provider"] = serde_json::json!(args.provider);
+        }
+        let query_args = args_from_json(&entry)?;
+        let composed = compose(&query_args, config)
+            .with_context(|| format!("Failed to compose batch entry {}", i + 1))?;
+
+        if dispatch.open && opened > 0 {
+            if opened % max_tabs == 0 && std::io::stdin().is_terminal() {
+                eprint!("Opened {} tabs, press enter to continue", opened);
+                std::io::stdin().read_line(&mut String::new())?;
+            } else {
+                std::thread::sleep(delay);
+            }
+        }
+        let url = dispatch.dispatch(&composed.provider, &composed.query)?;
+        if dispatch.open {
+            opened += 1;
+            dispatch.browser.new_window = false;
+        }
+        record_query(args, config, &composed, &url)?;
+        let path = batch::write_transcript(
+            &output,
+            i + 1,
+            &composed.provider.name,
+            &composed.prompt,
+            &composed.query,
+            &url,
+        )?;
+        output::note(format!("[{}] {}", i + 1, path.display()));
+        hook::completed(
+            &config.hook,
+            &serde_json::json!({
+                "index": i + 1,
+                "provider": composed.provider.name,
+                "prompt": composed.prompt,
+                "url": url,
+                "transcript": path,
+                "usage": {
+                    "bytes": composed.query.len(),
+                    "estimated_tokens": estimate_tokens(composed.query.len()),
+                },
+            }),
+        );
+    }
+    Ok(())
+}
+
+fn run_popup(args: &Args, config: &Config, capture: &Path) -> Result<()> {
+    let Some((output, prompt)) = popup::compose(capture)? else {
+        return Ok(());
     };
+    let query_args = Args::try_parse_from(["search", "--provider", &args.provider, "--", &prompt])?;
+    let pastes = vec![Paste {
+        source: "terminal",
+        content: output,
+    }];
+    let composed = compose_with(&query_args, config, pastes)?;
+    let url = dispatch_options(args, config).dispatch(&composed.provider, &composed.query)?;
+    record_query(args, config, &composed, &url)
+}
+
+fn run_review(
+    args: &Args,
+    config: &Config,
+    pr: Option<&str>,
+    base: Option<&str>,
+    prompt: &[String],
+) -> Result<()> {
+    let diff = review::diff(pr, base)?;
+    let mut instructions = review::PROMPT.to_string();
+    if !prompt.is_empty() {
+        instructions.push_str("\n\n");
+        instructions.push_str(&prompt.join(" "));
+    }
+
+    let mut argv = vec![
+        "search".to_string(),
+        "--provider".to_string(),
+        args.provider.clone(),
+    ];
+    if let Some(template) = &args.template {
+        argv.extend(["--template".to_string(), template.clone()]);
+    }
+    argv.extend(["--".to_string(), instructions]);
+    let query_args = Args::try_parse_from(argv)?;
+
+    let pastes = vec![Paste {
+        source: "diff",
+        content: diff,
+    }];
+    let composed = compose_with(&query_args, config, pastes)?;
+    let url = dispatch_options(args, config).dispatch(&composed.provider, &composed.query)?;
+    record_query(args, config, &composed, &url)
+}
+
+fn run_explain(args: &Args, config: &Config, command: &[String], how: Option<&str>) -> Result<()> {
+    let pastes = explain::gather(command)?;
+    let prompt = explain::prompt(command, how);
+    let query_args = Args::try_parse_from(["search", "--provider", &args.provider, "--", &prompt])?;
+    let composed = compose_with(&query_args, config, pastes)?;
+    let url = dispatch_options(args, config).dispatch(&composed.provider, &composed.query)?;
+    record_query(args, config, &composed, &url)
+}
+
+/// Shows what redaction replaced and asks whether to send the result.
+fn confirm_redactions(composed: &Composed) -> Result<bool> {
+    if composed.redactions.is_empty() {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("Redactions need confirming, but stdin is not a terminal");
+    }
+
+    for change in &composed.redactions {
+        output::diff(change.line, &change.before, &change.after);
+    }
+    ask("Send the redacted prompt?")
+}
+
+/// Lists what's about to be sent and asks first, if the prompt is over the configured limits.
+fn confirm_size(composed: &Composed, limits: &ConfirmConfig) -> Result<bool> {
+    let bytes = composed.query.len();
+    if !limits.exceeded(bytes) {
+        return Ok(true);
+    }
+    let tokens = estimate_tokens(bytes);
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "The prompt is {} (~{} tokens), over the [confirm] limit; pass --yes to send it",
+            format_size(bytes),
+            tokens
+        );
+    }
 
-    Ok(url)
+    output::warn(format!(
+        "About to send {} (~{} tokens) to {}:",
+        format_size(bytes),
+        tokens,
+        composed.provider.name
+    ));
+    for (source, size) in composed.sources.iter().zip(&composed.source_bytes) {
+        output::note(format!("  {}  {}", source, format_size(*size)));
+    }
+    if composed.provider.query_url(&composed.query).len() > composed.provider.max_url_length {
+        output::note("It's too long for a URL and will go through the clipboard.");
+    }
+    ask("Send it?")
+}
+
+fn ask(question: &str) -> Result<bool> {
+    eprint!("{} (y/N) ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok(answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes"))
+}
+
+fn run_import(file: &Path, config: &Config) -> Result<()> {
+    let imported = import::read(file)?;
+    let existing = history::load(&config.history)?;
+    // Importing the same export twice shouldn't duplicate its messages.
+    let entries: Vec<history::Entry> = imported
+        .entries
+        .into_iter()
+        .filter(|entry| {
+            !existing.iter().any(|e| {
+                e.url == entry.url && e.timestamp == entry.timestamp && e.prompt == entry.prompt
+            })
+        })
+        .collect();
+    let count = entries.len();
+    history::record_all(&config.history, entries)?;
+    output::note(format!(
+        "Imported {} message(s) from {} conversation(s)",
+        count, imported.conversations
+    ));
+    Ok(())
+}
+
+fn run_snip(action: &SnipAction, config: &Config) -> Result<()> {
+    match action {
+        SnipAction::Save {
+            name,
+            tag,
+            file,
+            from,
+            block,
+        } => {
+            let mut content = if let Some(id) = from {
+                let entry = history::load(&config.history)?
+                    .into_iter()
+                    .find(|e| e.id == *id)
+                    .with_context(|| format!("No history entry with id {}", id))?;
+                entry.answer.with_context(|| {
+                    format!(
+                        "History entry {} has no saved answer to take a snippet from",
+                        id
+                    )
+                })?
+            } else if let Some(file) = file {
+                std::fs::read_to_string(file)
+                    .with_context(|| format!("Failed to read {}", file.display()))?
+            } else if !std::io::stdin().is_terminal() {
+                std::io::read_to_string(std::io::stdin()).context("Failed to read stdin")?
+            } else {
+                SystemClipboard.get_text()?
+            };
+            if let Some(n) = *block {
+                if n == 0 {
+                    anyhow::bail!("--block counts from 1");
+                }
+                let blocks = snip::code_blocks(&content);
+                content = blocks
+                    .get(n - 1)
+                    .cloned()
+                    .with_context(|| format!("There are {} code blocks", blocks.len()))?;
+            }
+            if content.trim().is_empty() {
+                anyhow::bail!("Nothing to save; the snippet is empty");
+            }
+            let replaced = snip::save(name, content.trim_end(), tag.clone())?;
+            output::note(format!(
+                "{} snippet {}",
+                if replaced { "Replaced" } else { "Saved" },
+                name
+            ));
+        }
+        SnipAction::List { tag } => {
+            for (name, snippet) in snip::load()? {
+                if tag.as_ref().is_some_and(|tag| !snippet.tags.contains(tag)) {
+                    continue;
+                }
+                let tags = if snippet.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", snippet.tags.join(", "))
+                };
+                println!("{}{}  {}", name, tags, snippet.summary());
+            }
+        }
+        SnipAction::Copy { name } => {
+            SystemClipboard.set_text(&snip::get(name)?.content)?;
+            output::note(format!("Copied snippet {} to the clipboard", name));
+        }
+        SnipAction::Show { name } => println!("{}", snip::get(name)?.content),
+    }
+    Ok(())
 }
 
-fn run_search(input: &str, provider: &str) -> Result<()> {
-    let url = get_provider_url(provider, input)?;
-    webbrowser::open(&url).context("Failed to open browser")?;
+fn run_redact_test(file: &Path, config: &Config) -> Result<()> {
+    let text = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let dir = std::env::current_dir().unwrap_or_default();
+    let findings = redact::Redactor::new(&config.redact, &dir)?.find(&text);
+
+    for finding in &findings {
+        let before = &text[..finding.range.start];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        let matched: String = text[finding.range.clone()].chars().take(40).collect();
+        let status = match &finding.allowed_by {
+            Some(allow) => format!("allowed by {}", allow),
+            None => "redacted".to_string(),
+        };
+        println!(
+            "{}:{}:{}  {}  {}  {}",
+            file.display(),
+            line,
+            column,
+            finding.rule,
+            status,
+            matched.replace('\n', " ")
+        );
+    }
+
+    let allowed = findings.iter().filter(|f| f.allowed_by.is_some()).count();
+    output::note(format!(
+        "{} match(es), {} redacted, {} allowed",
+        findings.len(),
+        findings.len() - allowed,
+        allowed
+    ));
     Ok(())
 }
 
-fn run_contextualize(files: &[String]) -> Result<()> {
-    use std::process::Command;
+fn run_template(action: &TemplateAction, config: &Config) -> Result<()> {
+    match action {
+        TemplateAction::Sync => {
+            let dir = library::sync(&config.library)?;
+            output::note(format!("Template library synced to {}", dir.display()));
+            Ok(())
+        }
+        TemplateAction::List => {
+            let (templates, bundles) = library::list(&config.library)?;
+            println!("templates: {}", templates.join(", "));
+            println!("bundles:   {}", bundles.join(", "));
+            Ok(())
+        }
+    }
+}
+
+fn mcp_tools() -> Vec<mcp::Tool> {
+    let properties = serde_json::json!({
+        "prompt": { "type": "string", "description": "Question or instruction" },
+        "context": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Files and directories to include as context",
+        },
+        "deps": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Files to include along with the local modules they import",
+        },
+        "provider": { "type": "string", "description": "Provider name, e.g. chatgpt or claude" },
+        "style": { "type": "string", "enum": ["xml", "markdown", "keywords"] },
+        "template": { "type": "string", "description": "Library template to wrap the prompt in" },
+        "lang": { "type": "string", "description": "Language to answer in, e.g. de or German" },
+        "manifest": { "type": "boolean", "description": "Include the project manifest" },
+        "line_numbers": { "type": "boolean" },
+        "minify": { "type": "boolean" },
+        "budget": { "type": "integer", "description": "Maximum bytes of file context" },
+    });
+    let mut search_properties = properties.clone();
+    search_properties["open"] = serde_json::json!({
+        "type": "boolean",
+        "description": "Open the URL in the user's browser (default true)",
+    });
+
+    vec![
+        mcp::Tool {
+            name: "compose",
+            description: "Collect context, redact secrets, and format a prompt without sending it",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": properties,
+                "required": ["prompt"],
+            }),
+        },
+        mcp::Tool {
+            name: "search",
+            description: "Compose a prompt and open it with an LLM provider, returning the URL",
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": search_properties,
+                "required": ["prompt"],
+            }),
+        },
+    ]
+}
+
+fn args_from_json(arguments: &serde_json::Value) -> Result<Args> {
+    let mut argv = vec!["search".to_string(), "--no-open".to_string()];
+    let string = |key: &str| arguments[key].as_str().map(str::to_string);
+    let list = |key: &str| -> Vec<String> {
+        match &arguments[key] {
+            serde_json::Value::String(item) => vec![item.clone()],
+            serde_json::Value::Array(items) => items
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+
+    let context = list("context");
+    if !context.is_empty() {
+        argv.push("--context".to_string());
+        argv.extend(context);
+    }
+    for dep in list("deps") {
+        argv.extend(["--deps".to_string(), dep]);
+    }
+    for (flag, key) in [
+        ("--provider", "provider"),
+        ("--style", "style"),
+        ("--template", "template"),
+        ("--lang", "lang"),
+        ("--region", "region"),
+        ("--locale", "locale"),
+    ] {
+        if let Some(value) = string(key) {
+            argv.extend([flag.to_string(), value]);
+        }
+    }
+    for (flag, key) in [
+        ("--manifest", "manifest"),
+        ("--line-numbers", "line_numbers"),
+        ("--minify", "minify"),
+    ] {
+        if arguments[key].as_bool() == Some(true) {
+            argv.push(flag.to_string());
+        }
+    }
+    if let Some(budget) = arguments["budget"].as_u64() {
+        argv.extend(["--budget".to_string(), budget.to_string()]);
+    }
+    argv.push("--".to_string());
+    argv.push(string("prompt").unwrap_or_default());
+
+    Args::try_parse_from(argv).map_err(|e| anyhow::anyhow!("{}", e.to_string().trim()))
+}
+
+/// Composes from JSON arguments and, if `send` is set, records the query and optionally opens it.
+fn dispatch_json(
+    arguments: &serde_json::Value,
+    config: &Config,
+    send: bool,
+    open: bool,
+) -> Result<(Composed, String)> {
+    let args = args_from_json(arguments)?;
+    let composed = compose(&args, config)?;
+    let url = composed.provider.query_url(&composed.query);
+    if !send {
+        return Ok((composed, url));
+    }
+
+    if open {
+        let dispatch = UrlDispatcher {
+            open: true,
+            print_url: false,
+            copy_url: false,
+            qr: false,
+            browser: browser_options(None, config),
+        };
+        dispatch.dispatch(&composed.provider, &composed.query)?;
+    }
+    record_query(&args, config, &composed, &url)?;
+    Ok((composed, url))
+}
+
+fn call_mcp_tool(name: &str, arguments: &serde_json::Value, config: &Config) -> Result<String> {
+    let open = arguments["open"].as_bool().unwrap_or(true);
+    let (composed, url) = dispatch_json(arguments, config, name == "search", open)?;
+    match name {
+        "compose" => Ok(composed.query),
+        _ => Ok(url),
+    }
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(args: &Args, config: &Config) -> Result<()> {
+    let preselect = args.context.is_some();
+    let paths = args
+        .context
+        .clone()
+        .unwrap_or_else(|| vec![".".to_string()]);
+    let Some(selection) = search::tui::run(
+        config,
+        compose_options(args),
+        &paths,
+        preselect,
+        args.max_file_bytes,
+    )?
+    else {
+        return Ok(());
+    };
+
+    let mut args = args.clone();
+    args.command = None;
+    args.provider = selection.provider;
+    args.prompt = vec![selection.prompt];
+    args.context = (!selection.files.is_empty()).then_some(selection.files);
+    run_query(&args, config)
+}
+
+#[cfg(feature = "daemon")]
+fn run_daemon_query(args: &Args, config: &Config, selection: String, prompt: String) -> Result<()> {
+    let query_args = Args::try_parse_from(["search", "--provider", &args.provider, "--", &prompt])?;
+    let pastes = vec![Paste {
+        source: "selection",
+        content: selection,
+    }];
+    let composed = compose_with(&query_args, config, pastes)?;
+    // The daemon has no terminal, so open the browser unless told not to.
+    let mut dispatch = dispatch_options(args, config);
+    dispatch.open = !args.no_open;
+    let url = dispatch.dispatch(&composed.provider, &composed.query)?;
+    record_query(args, config, &composed, &url)
+}
 
-    let mut cmd = Command::new("contextualize");
-    cmd.arg("cat").arg("--output").arg("clipboard");
-    cmd.args(files);
+fn handle_native_message(
+    message: &serde_json::Value,
+    args: &Args,
+    config: &Config,
+) -> Result<serde_json::Value> {
+    let query_args = args_from_json(message)?;
+    let mut pastes = Vec::new();
+    if let Some(selection) = message["selection"].as_str().filter(|s| !s.is_empty()) {
+        pastes.push(Paste {
+            source: "selection",
+            content: selection.to_string(),
+        });
+    }
+    if let Some(page) = message["page"].as_object() {
+        let field = |key: &str| page.get(key).and_then(|v| v.as_str()).unwrap_or("");
+        let content = [field("title"), field("url"), field("text")]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        pastes.push(Paste {
+            source: "page",
+            content,
+        });
+    }
 
-    let output = cmd
-        .output()
-        .context("Failed to run contextualize command")?;
+    let composed = compose_with(&query_args, config, pastes)?;
+    let provider = &composed.provider;
+    let url = provider.query_url(&composed.query);
+    record_query(args, config, &composed, &url)?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("contextualize command failed: {}", stderr);
+    // The extension opens or reuses a tab on the provider's origin.
+    if url.len() <= provider.max_url_length {
+        Ok(serde_json::json!({
+            "action": "open",
+            "provider": provider.name,
+            "url": url,
+            "reuse": provider.home,
+        }))
+    } else {
+        Ok(serde_json::json!({
+            "action": "paste",
+            "provider": provider.name,
+            "url": provider.home,
+            "reuse": provider.home,
+            "prompt": composed.query,
+        }))
     }
+}
 
+fn handle_url(link: &str, config: &Config) -> Result<()> {
+    let mut request = scheme::parse(link)?;
+    let result = confirm_link_context(&mut request)
+        .and_then(|()| dispatch_json(&request.arguments, config, true, true));
+    match result {
+        Ok((composed, _)) => {
+            // Links can come from anywhere, so don't hand the composed context to the caller.
+            if let Some(success) = &request.success {
+                scheme::callback(success, &[("provider", &composed.provider.name)])?;
+            }
+            Ok(())
+        }
+        Err(e) => {
+            if let Some(error) = &request.error {
+                let message = format!("{:#}", e);
+                scheme::callback(error, &[("errorMessage", &message)])?;
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Asks before a link reads files, which it can only name under the current directory.
+fn confirm_link_context(request: &mut scheme::Request) -> Result<()> {
+    let dir = std::env::current_dir().context("Failed to read the current directory")?;
+    let paths = scheme::confine_context(request, &dir)?;
+    if paths.is_empty() {
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "search:// links can only include files when run from a terminal to confirm them"
+        );
+    }
+    let list: Vec<String> = paths.iter().map(|p| format!("  {}", p.display())).collect();
+    output::note(format!("The link asks to include:\n{}", list.join("\n")));
+    if !ask("Send these files?")? {
+        anyhow::bail!("Cancelled");
+    }
     Ok(())
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+fn handle_serve_query(input: &serde_json::Value, config: &Config) -> Result<serde_json::Value> {
+    let action = input["action"].as_str().unwrap_or("url");
+    let (send, open) = match action {
+        "compose" => (false, false),
+        "url" => (true, false),
+        "open" => (true, true),
+        "api" => anyhow::bail!("API mode is not available; use \"url\" or \"open\""),
+        other => anyhow::bail!("Unknown action {:?}, expected compose, url, or open", other),
+    };
+    let (composed, url) = dispatch_json(input, config, send, open)?;
+    Ok(serde_json::json!({
+        "provider": composed.provider.name,
+        "prompt": composed.query,
+        "url": url,
+        "sources": composed.sources,
+    }))
+}
 
-    if args.clipboard && args.context.is_some() {
-        anyhow::bail!("--clipboard and --context flags are not compatible");
+fn dispatch_options(args: &Args, config: &Config) -> UrlDispatcher {
+    // Piped output gets the URL instead of a browser tab, e.g. `search ... | pbcopy`.
+    let interactive = args.open || std::io::stdout().is_terminal();
+    UrlDispatcher {
+        open: !args.no_open && interactive,
+        print_url: args.print_url,
+        copy_url: args.copy_url,
+        qr: args.qr,
+        browser: browser_options(Some(args), config),
     }
+}
 
-    let content = if let Some(files) = args.context {
-        run_contextualize(&files)?;
-        get_clipboard_content()?
-    } else if args.clipboard {
-        get_clipboard_content()?
+fn browser_options(args: Option<&Args>, config: &Config) -> BrowserOptions {
+    BrowserOptions {
+        browser: args
+            .and_then(|a| a.browser.clone())
+            .or_else(|| config.browser.name.clone()),
+        profile: args
+            .and_then(|a| a.browser_profile.clone())
+            .or_else(|| config.browser.profile.clone()),
+        incognito: args.is_some_and(|a| a.incognito) || config.browser.incognito,
+        new_window: false,
+    }
+}
+
+fn run_history(action: Option<&HistoryAction>, args: &Args, config: &Config) -> Result<()> {
+    match action {
+        None => print_history(args, config, &[], 20),
+        Some(HistoryAction::List { query, limit }) => print_history(args, config, query, *limit),
+        Some(HistoryAction::Show { id }) => {
+            let entry = history::find(&config.history, *id)?;
+            println!("id:       {}", entry.id);
+            println!("time:     {}", entry.local_time());
+            println!("provider: {}", entry.provider);
+            println!("prompt:   {}", entry.prompt);
+            if !entry.sources.is_empty() {
+                println!("sources:  {}", entry.sources.join(", "));
+            }
+            println!("url:      {}", entry.url);
+            println!();
+            println!("{}", entry.composed);
+            if let Some(answer) = &entry.answer {
+                println!();
+                println!("{}", output::bold("answer:"));
+                println!("{}", answer);
+            }
+            Ok(())
+        }
+        Some(HistoryAction::Rerun { id, provider }) => {
+            let entry = history::find(&config.history, *id)?;
+            let name = provider.clone().unwrap_or_else(|| entry.provider.clone());
+            resend(entry, &name, args, config)
+        }
+        Some(HistoryAction::Sessions) => {
+            for session in history::sessions(&history::load(&config.history)?) {
+                println!(
+                    "{:<20}  {:>4} queries  last {}  {}",
+                    session.name,
+                    session.queries,
+                    session.last.local_time(),
+                    session.last.summary()
+                );
+            }
+            Ok(())
+        }
+        Some(HistoryAction::Export { format }) => {
+            let entries = in_session(history::load(&config.history)?, args);
+            history::export(&entries, *format, &mut std::io::stdout().lock())
+        }
+        Some(HistoryAction::Purge { before }) => {
+            let date = NaiveDate::parse_from_str(before, "%Y-%m-%d")
+                .with_context(|| format!("Invalid date {:?}, expected YYYY-MM-DD", before))?;
+            let before = date
+                .and_time(NaiveTime::MIN)
+                .and_local_timezone(Local)
+                .earliest()
+                .context("Date does not exist in the local time zone")?
+                .with_timezone(&Utc);
+            let removed = history::purge(&config.history, before)?;
+            output::note(format!("Removed {} history entries", removed));
+            let removed = ring::purge(&config.history, before)?;
+            if removed > 0 {
+                output::note(format!("Removed {} clipboard captures", removed));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn in_session(mut entries: Vec<history::Entry>, args: &Args) -> Vec<history::Entry> {
+    if let Some(session) = &args.session {
+        entries.retain(|entry| entry.session.as_ref() == Some(session));
+    }
+    entries
+}
+
+fn print_history(args: &Args, config: &Config, query: &[String], limit: usize) -> Result<()> {
+    let mut entries = in_session(history::load(&config.history)?, args);
+    // Imports are appended with the older times of their conversations.
+    entries.sort_by_key(|entry| entry.timestamp);
+    let query = query.join(" ");
+    let matches: Vec<&history::Entry> = if query.is_empty() {
+        entries.iter().rev().collect()
     } else {
-        String::new()
+        history::search(&entries, &query)
     };
 
-    if !content.is_empty() {
-        let formatted = format_content(&content, &args.prompt);
-        run_search(&formatted, &args.provider)?;
+    for entry in matches.into_iter().take(limit) {
+        println!(
+            "{:>5}  {}  {:<10}  {}",
+            entry.id,
+            entry.local_time(),
+            entry.provider,
+            entry.summary()
+        );
+    }
+    Ok(())
+}
+
+fn resend(entry: history::Entry, provider: &str, args: &Args, config: &Config) -> Result<()> {
+    let provider = UrlProvider::resolve(provider, config)?;
+    let url = dispatch_options(args, config).dispatch(&provider, &entry.composed)?;
+    if args.no_history {
+        return Ok(());
+    }
+    let mut new = history::Entry::new(&provider.name, &entry.prompt, &entry.composed, &url);
+    new.sources = entry.sources;
+    new.session = args.session.clone().or(entry.session);
+    new.template = entry.template;
+    history::record(&config.history, new)?;
+    Ok(())
+}
+
+const DUPLICATE_WINDOW_HOURS: u64 = 6;
+
+fn run_query(args: &Args, config: &Config) -> Result<()> {
+    if args.last {
+        let entry = history::last(&config.history, args.session.as_deref())?
+            .context("No previous query in history")?;
+        return resend(entry, &args.provider, args, config);
+    }
+
+    let mut composed = compose(args, config)?;
+
+    if (args.confirm_redactions || config.redact.confirm) && !confirm_redactions(&composed)? {
+        output::note("Nothing was sent");
+        return Ok(());
+    }
+    if !args.yes && args.format.is_none() && !confirm_size(&composed, &config.confirm)? {
+        output::note("Nothing was sent");
+        return Ok(());
+    }
+
+    let query = &composed.query;
+    if let Some(path) = &args.tee {
+        std::fs::write(path, query)
+            .with_context(|| format!("Failed to write prompt to {}", path.display()))?;
+    }
+
+    if let Some(format) = args.format {
+        let preview = launcher::render(format, &composed.provider, &composed.prompt, query);
+        println!("{}", serde_json::to_string_pretty(&preview)?);
+        return Ok(());
+    }
+
+    if !args.force {
+        let hours = config
+            .duplicate_window_hours
+            .unwrap_or(DUPLICATE_WINDOW_HOURS);
+        if let Some(previous) = history::find_recent(&config.history, query, hours)? {
+            let mut message = format!(
+                "The same prompt was sent to {} at {} (history id {})",
+                previous.provider,
+                previous.local_time(),
+                previous.id
+            );
+            if let Some(saved) = &previous.saved {
+                message.push_str(&format!(", saved to {}", saved.display()));
+            }
+            anyhow::bail!("{}; pass --force to send it again", message);
+        }
+    }
+
+    let overflows = composed.provider.query_url(query).len() > composed.provider.max_url_length;
+    if overflows && (args.paste_overflow || config.paste.auto) && !args.webdriver && !args.desktop {
+        match paste::upload(&config.paste, query) {
+            Ok(link) => {
+                output::note(format!("Context uploaded to {}", link));
+                composed.query = paste::prompt(&composed.prompt, &link);
+            }
+            Err(e) => output::warn(format!("{:#}; sending the prompt as usual", e)),
+        }
+    }
+
+    if let Some(chat) = &args.chat {
+        composed.provider.open_chat(chat)?;
+    }
+
+    let dispatcher: Box<dyn Dispatcher> = if args.webdriver {
+        Box::new(WebDriverDispatcher {
+            config: &config.webdriver,
+        })
+    } else if args.desktop {
+        Box::new(DesktopDispatcher {
+            submit: args.submit,
+        })
     } else {
-        let query = args.prompt.join(" ");
-        run_search(&query, &args.provider)?;
+        Box::new(dispatch_options(args, config))
+    };
+    let url = dispatcher.dispatch(&composed.provider, &composed.query)?;
+    record_query(args, config, &composed, &url)
+}
+
+fn record_query(args: &Args, config: &Config, composed: &Composed, url: &str) -> Result<()> {
+    if args.no_history || composed.redacted {
+        return Ok(());
     }
 
+    let mut entry = history::Entry::new(
+        &composed.provider.name,
+        &composed.prompt,
+        &composed.query,
+        url,
+    );
+    entry.sources = composed.sources.clone();
+    entry.saved = args.tee.clone();
+    entry.session = args.session.clone();
+    entry.template = args.template.clone();
+    history::record(&config.history, entry)?;
     Ok(())
 }
+
+/// Ctrl-C while contextualize runs stops it and reports its stderr, otherwise it exits as usual.
+fn handle_interrupts() {
+    static CTRLC: Once = Once::new();
+    CTRLC.call_once(|| {
+        let _ = ctrlc::set_handler(|| {
+            if !source::interrupt() {
+                std::process::exit(130);
+            }
+        });
+    });
+}
+
+fn compose(args: &Args, config: &Config) -> Result<Composed> {
+    compose_with(args, config, Vec::new())
+}
+
+fn compose_with(args: &Args, config: &Config, pastes: Vec<Paste>) -> Result<Composed> {
+    if args.clipboard.is_some() && args.context.is_some() {
+        anyhow::bail!("--clipboard and --context flags are not compatible");
+    }
+
+    let mut sources: Vec<Box<dyn ContextSource>> = Vec::new();
+    for paste in pastes {
+        sources.push(Box::new(paste));
+    }
+
+    if args.ground {
+        sources.push(Box::new(source::Ground {
+            query: args.prompt.join(" "),
+        }));
+    }
+
+    let mut context = args
+        .context
+        .as_ref()
+        .map(|paths| paths.iter().map(|path| wsl::translate_path(path)).collect());
+    if let Some(bundle) = &args.bundle {
+        context
+            .get_or_insert_with(Vec::new)
+            .extend(library::bundle(&config.library, bundle)?);
+    }
+    if let Some(paths) = context {
+        if args.contextualize {
+            handle_interrupts();
+            sources.push(Box::new(source::Contextualize { paths }));
+        } else {
+            sources.push(Box::new(source::Paths {
+                paths,
+                max_file_bytes: args.max_file_bytes,
+            }));
+        }
+    } else if let Some(index) = args.clipboard {
+        sources.push(Box::new(source::ClipboardRing {
+            clipboard: source::Clipboard(Box::new(SystemClipboard)),
+            index,
+            record: !args.no_history,
+        }));
+    }
+
+    for path in &args.tail {
+        sources.push(Box::new(source::Tail {
+            path: path.clone(),
+            lines: args.tail_lines,
+            since: args.since,
+            max_file_bytes: args.max_file_bytes,
+        }));
+    }
+    if let Some(pattern) = &args.env {
+        sources.push(Box::new(source::Env {
+            pattern: pattern.clone(),
+            values: args.env_values,
+        }));
+    }
+    if args.sysinfo {
+        sources.push(Box::new(source::SysInfo));
+    }
+    for container in &args.docker_logs {
+        sources.push(Box::new(source::DockerLogs {
+            container: container.clone(),
+            lines: args.tail_lines,
+            since: args.since,
+            max_file_bytes: args.max_file_bytes,
+        }));
+    }
+    for command in &args.kubectl {
+        sources.push(Box::new(source::Kubectl {
+            command: command.clone(),
+            max_file_bytes: args.max_file_bytes,
+        }));
+    }
+    for root in &args.deps {
+        sources.push(Box::new(source::Deps {
+            root: PathBuf::from(root),
+            max_file_bytes: args.max_file_bytes,
+        }));
+    }
+    if args.definitions {
+        sources.push(Box::new(source::Definitions));
+    }
+    if args.manifest {
+        sources.push(Box::new(source::Manifest {
+            max_file_bytes: args.max_file_bytes,
+        }));
+    }
+
+    Ok(pipeline::compose(&compose_options(args), &sources, config)?)
+}
+
+fn compose_options(args: &Args) -> ComposeOptions {
+    ComposeOptions {
+        provider: args.provider.clone(),
+        prompt: args.prompt.join(" "),
+        template: args.template.clone(),
+        style: args.style,
+        line_numbers: args.line_numbers,
+        order: args.order,
+        source_order: args.source_order.clone(),
+        lang: args.lang.clone(),
+        region: args.region.clone(),
+        locale: args.locale.clone(),
+        minify: args.minify,
+        budget: args.budget,
+        summarize: args.summarize,
+        raw: args.raw,
+        no_redact: args.no_redact,
+        repo_header: !args.no_repo_header,
+    }
+}
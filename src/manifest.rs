@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::context::{self, ContextFile};
+
+const MANIFESTS: &[(&str, &[&str])] = &[
+    ("Cargo.toml", &["Cargo.lock"]),
+    ("package.json", &["package-lock.json"]),
+    ("pyproject.toml", &["uv.lock", "poetry.lock"]),
+];
+
+pub fn collect(max_file_bytes: usize) -> Result<Vec<ContextFile>> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    collect_in(&cwd, max_file_bytes)
+}
+
+/// The manifests of the project containing `dir`, each followed by a summary of its lockfile.
+pub fn collect_in(dir: &Path, max_file_bytes: usize) -> Result<Vec<ContextFile>> {
+    let Some(root) = find_root(dir) else {
+        anyhow::bail!(
+            "No Cargo.toml, package.json, or pyproject.toml found in this or any parent directory"
+        );
+    };
+
+    let mut files = Vec::new();
+    for (manifest, lockfiles) in MANIFESTS {
+        let manifest_path = root.join(manifest);
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let manifest_file = context::read_path(&manifest_path, max_file_bytes)?;
+        let direct = direct_dependencies(manifest, &manifest_file.content);
+
+        let lockfile = lockfiles
+            .iter()
+            .map(|name| root.join(name))
+            .find(|p| p.is_file());
+        files.push(manifest_file);
+
+        if let Some(lock_path) = lockfile {
+            files.push(summarize_lockfile(&lock_path, &direct)?);
+        }
+    }
+
+    Ok(files)
+}
+
+/// The manifests and lockfiles `collect` reads from `dir`, which may be in a parent directory.
+pub fn files(dir: &Path) -> Vec<PathBuf> {
+    let Some(root) = find_root(dir) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for (manifest, lockfiles) in MANIFESTS {
+        let manifest_path = root.join(manifest);
+        if !manifest_path.is_file() {
+            continue;
+        }
+        files.push(manifest_path);
+        files.extend(
+            lockfiles
+                .iter()
+                .map(|name| root.join(name))
+                .find(|p| p.is_file()),
+        );
+    }
+    files
+}
+
+fn find_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| MANIFESTS.iter().any(|(name, _)| dir.join(name).is_file()))
+        .map(Path::to_path_buf)
+}
+
+fn direct_dependencies(manifest: &str, content: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+
+    match manifest {
+        "Cargo.toml" => {
+            let Ok(doc) = content.parse::<toml::Table>() else {
+                return names;
+            };
+            let tables = ["dependencies", "dev-dependencies", "build-dependencies"];
+            for table in tables {
+                if let Some(deps) = doc.get(table).and_then(|v| v.as_table()) {
+                    names.extend(deps.keys().cloned());
+                }
+            }
+            if let Some(deps) = doc
+                .get("workspace")
+                .and_then(|w| w.get("dependencies"))
+                .and_then(|v| v.as_table())
+            {
+                names.extend(deps.keys().cloned());
+            }
+        }
+        "package.json" => {
+            let Ok(doc) = serde_json::from_str::<serde_json::Value>(content) else {
+                return names;
+            };
+            for key in ["dependencies", "devDependencies", "peerDependencies"] {
+                if let Some(deps) = doc.get(key).and_then(|v| v.as_object()) {
+                    names.extend(deps.keys().cloned());
+                }
+            }
+        }
+        "pyproject.toml" => {
+            let Ok(doc) = content.parse::<toml::Table>() else {
+                return names;
+            };
+            if let Some(deps) = doc
+                .get("project")
+                .and_then(|p| p.get("dependencies"))
+                .and_then(|v| v.as_array())
+            {
+                names.extend(deps.iter().filter_map(|d| d.as_str()).map(pep508_name));
+            }
+            if let Some(deps) = doc
+                .get("tool")
+                .and_then(|t| t.get("poetry"))
+                .and_then(|p| p.get("dependencies"))
+                .and_then(|v| v.as_table())
+            {
+                names.extend(
+                    deps.keys()
+                        .filter(|k| *k != "python")
+                        .map(|k| normalize_python(k)),
+                );
+            }
+        }
+        _ => {}
+    }
+
+    names
+}
+
+fn pep508_name(requirement: &str) -> String {
+    let end = requirement
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .unwrap_or(requirement.len());
+    normalize_python(&requirement[..end])
+}
+
+fn normalize_python(name: &str) -> String {
+    name.to_lowercase().replace(['_', '.'], "-")
+}
+
+fn locked_packages(lock_path: &Path, content: &str) -> Result<BTreeMap<String, String>> {
+    let mut packages = BTreeMap::new();
+    let name = lock_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+    if name == "package-lock.json" {
+        let doc: serde_json::Value = serde_json::from_str(content)
+            .with_context(|| format!("Failed to parse {}", lock_path.display()))?;
+        if let Some(entries) = doc.get("packages").and_then(|v| v.as_object()) {
+            for (key, entry) in entries {
+                let Some(pkg) = key
+                    .strip_prefix("node_modules/")
+                    .filter(|pkg| !pkg.contains("/node_modules/"))
+                else {
+                    continue;
+                };
+                if let Some(version) = entry.get("version").and_then(|v| v.as_str()) {
+                    packages.insert(pkg.to_string(), version.to_string());
+                }
+            }
+        }
+    } else {
+        let doc: toml::Table = content
+            .parse()
+            .with_context(|| format!("Failed to parse {}", lock_path.display()))?;
+        let python = name != "Cargo.lock";
+        if let Some(entries) = doc.get("package").and_then(|v| v.as_array()) {
+            for entry in entries {
+                let (Some(pkg), Some(version)) = (
+                    entry.get("name").and_then(|v| v.as_str()),
+                    entry.get("version").and_then(|v| v.as_str()),
+                ) else {
+                    continue;
+                };
+                let pkg = if python {
+                    normalize_python(pkg)
+                } else {
+                    pkg.to_string()
+                };
+                packages.insert(pkg, version.to_string());
+            }
+        }
+    }
+
+    Ok(packages)
+}
+
+fn summarize_lockfile(lock_path: &Path, direct: &BTreeSet<String>) -> Result<ContextFile> {
+    let content = std::fs::read_to_string(lock_path)
+        .with_context(|| format!("Failed to read {}", lock_path.display()))?;
+    let packages = locked_packages(lock_path, &content)?;
+
+    let resolved: Vec<String> = direct
+        .iter()
+        .filter_map(|name| {
+            packages
+                .get(name)
+                .map(|version| format!("{} {}", name, version))
+        })
+        .collect();
+    let summary = if resolved.is_empty() {
+        "No direct dependencies resolved".to_string()
+    } else {
+        format!("Resolved direct dependencies:\n{}", resolved.join("\n"))
+    };
+
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    Ok(ContextFile {
+        path: context::relative_path(lock_path, &cwd),
+        size: content.len(),
+        content: summary,
+        lang: None,
+        truncated: false,
+        note: Some(format!("summary of {} locked packages", packages.len())),
+    })
+}
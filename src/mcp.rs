@@ -0,0 +1,94 @@
+use std::io::{BufRead, Write};
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+pub struct Tool {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub input_schema: Value,
+}
+
+/// Serves Model Context Protocol requests over stdio until stdin closes.
+pub fn serve(tools: &[Tool], mut call: impl FnMut(&str, &Value) -> Result<String>) -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = line.context("Failed to read from stdin")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle(&message, tools, &mut call),
+            Err(e) => Some(error(Value::Null, -32700, &format!("Parse error: {}", e))),
+        };
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response).context("Failed to write to stdout")?;
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle(
+    message: &Value,
+    tools: &[Tool],
+    call: &mut impl FnMut(&str, &Value) -> Result<String>,
+) -> Option<Value> {
+    // Notifications carry no id and get no response.
+    let id = message.get("id")?.clone();
+    let method = message["method"].as_str().unwrap_or("");
+    let params = &message["params"];
+
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "search", "version": env!("CARGO_PKG_VERSION") },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({
+            "tools": tools
+                .iter()
+                .map(|tool| json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "inputSchema": tool.input_schema,
+                }))
+                .collect::<Vec<_>>(),
+        }),
+        "tools/call" => {
+            let name = params["name"].as_str().unwrap_or("");
+            if !tools.iter().any(|tool| tool.name == name) {
+                return Some(error(id, -32602, &format!("Unknown tool: {}", name)));
+            }
+            let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+            match call(name, &arguments) {
+                Ok(text) => json!({
+                    "content": [{ "type": "text", "text": text }],
+                    "isError": false,
+                }),
+                Err(e) => json!({
+                    "content": [{ "type": "text", "text": format!("{:#}", e) }],
+                    "isError": true,
+                }),
+            }
+        }
+        _ => return Some(error(id, -32601, &format!("Method not found: {}", method))),
+    };
+
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
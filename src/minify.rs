@@ -0,0 +1,103 @@
+const LICENSE_MARKERS: &[&str] = &[
+    "license",
+    "copyright",
+    "spdx-license-identifier",
+    "all rights reserved",
+    "(c)",
+];
+
+/// Drops license headers, comment banners, and repeated blank lines from a file in `lang`.
+pub fn minify(content: &str, lang: Option<&str>) -> String {
+    let mut lines: Vec<&str> = content.lines().map(str::trim_end).collect();
+
+    let start = usize::from(lines.first().is_some_and(|l| l.starts_with("#!")));
+    let header = leading_comment_len(&lines[start..]);
+    if header > 0 {
+        let text = lines[start..start + header].join("\n").to_lowercase();
+        if LICENSE_MARKERS.iter().any(|marker| text.contains(marker)) {
+            lines.drain(start..start + header);
+        }
+    }
+
+    let mut out = String::with_capacity(content.len());
+    let markers = comment_markers(lang);
+    let mut blank_run = 0;
+    for line in lines {
+        if is_banner(line, markers) {
+            continue;
+        }
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out.trim_matches('\n').to_string() + "\n"
+}
+
+fn leading_comment_len(lines: &[&str]) -> usize {
+    let Some(first) = lines.first().map(|l| l.trim_start()) else {
+        return 0;
+    };
+
+    const BLOCKS: &[(&str, &str)] = &[
+        ("/*", "*/"),
+        ("<!--", "-->"),
+        ("\"\"\"", "\"\"\""),
+        ("'''", "'''"),
+    ];
+    if let Some((open, close)) = BLOCKS.iter().find(|(open, _)| first.starts_with(open)) {
+        if first[open.len()..].contains(close) {
+            return 1;
+        }
+        return lines
+            .iter()
+            .skip(1)
+            .position(|l| l.contains(close))
+            .map_or(0, |i| i + 2);
+    }
+
+    let marker = ["//", "#", "--", ";"]
+        .into_iter()
+        .find(|m| first.starts_with(m));
+    match marker {
+        Some(marker) => lines
+            .iter()
+            .take_while(|l| l.trim_start().starts_with(marker))
+            .count(),
+        None => 0,
+    }
+}
+
+/// How comment lines start in the language, empty where a banner-like line could be content,
+/// like a Markdown underline or a YAML document separator.
+fn comment_markers(lang: Option<&str>) -> &'static [&'static str] {
+    match lang.unwrap_or_default() {
+        "rust" | "javascript" | "jsx" | "typescript" | "tsx" | "go" | "c" | "cpp" | "csharp"
+        | "java" | "kotlin" | "swift" | "php" | "scala" | "dart" | "zig" | "css" | "scss" => {
+            &["//", "/*", "*"]
+        }
+        "python" | "ruby" | "bash" | "zsh" | "fish" | "powershell" | "yaml" | "toml"
+        | "makefile" | "dockerfile" | "cmake" | "nix" | "elixir" => &["#"],
+        "sql" | "lua" | "haskell" => &["--"],
+        "erlang" => &["%"],
+        "clojure" => &[";"],
+        _ => &[],
+    }
+}
+
+fn is_banner(line: &str, markers: &[&str]) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= 8
+        && markers.iter().any(|marker| trimmed.starts_with(marker))
+        && trimmed
+            .chars()
+            .all(|c| "/#*-=~_+;%".contains(c) || c == ' ')
+        && trimmed.chars().filter(|c| *c != ' ').count() >= 8
+}
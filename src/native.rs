@@ -0,0 +1,136 @@
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+pub const HOST_NAME: &str = "com.conscious_data.search";
+
+// Browsers refuse messages to the extension larger than this.
+const MAX_OUTGOING: usize = 1024 * 1024;
+// The extension only sends prompts and page selections, so a huge length is a broken peer.
+const MAX_INCOMING: usize = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Browser {
+    Chrome,
+    Chromium,
+    Firefox,
+}
+
+/// Answers length-prefixed JSON messages from a browser extension until it disconnects.
+pub fn serve(mut handle: impl FnMut(&Value) -> Result<Value>) -> Result<()> {
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+
+    while let Some(message) = read_message(&mut stdin)? {
+        let response = match handle(&message) {
+            Ok(response) => response,
+            Err(e) => json!({ "action": "error", "error": format!("{:#}", e) }),
+        };
+        write_message(&mut stdout, &response)?;
+    }
+    Ok(())
+}
+
+fn read_message(reader: &mut impl Read) -> Result<Option<Value>> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e).context("Failed to read message length"),
+    }
+
+    let length = u32::from_ne_bytes(length) as usize;
+    if length > MAX_INCOMING {
+        anyhow::bail!(
+            "Message of {} bytes is over the {} byte limit",
+            length,
+            MAX_INCOMING
+        );
+    }
+    let mut body = vec![0u8; length];
+    reader
+        .read_exact(&mut body)
+        .context("Failed to read message")?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .context("Message is not valid JSON")
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let mut body = serde_json::to_vec(message)?;
+    if body.len() > MAX_OUTGOING {
+        body = serde_json::to_vec(&json!({
+            "action": "error",
+            "error": "Response is too large for native messaging",
+        }))?;
+    }
+    writer.write_all(&(body.len() as u32).to_ne_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Registers the host with a browser for one extension, returning the manifest path.
+pub fn install(browser: Browser, extension_id: &str) -> Result<PathBuf> {
+    let dir = manifest_dir(browser)
+        .context("Native messaging hosts can only be installed on Linux and macOS")?;
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    // Browsers launch the host without arguments of our choosing, so point them at a wrapper.
+    let exe = std::env::current_exe().context("Failed to locate the search executable")?;
+    let wrapper = dir.join(format!("{}.sh", HOST_NAME));
+    std::fs::write(
+        &wrapper,
+        format!("#!/bin/sh\nexec '{}' native-host \"$@\"\n", exe.display()),
+    )
+    .with_context(|| format!("Failed to write {}", wrapper.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&wrapper, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": "Compose LLM queries with search",
+        "path": wrapper,
+        "type": "stdio",
+    });
+    match browser {
+        Browser::Firefox => manifest["allowed_extensions"] = json!([extension_id]),
+        Browser::Chrome | Browser::Chromium => {
+            manifest["allowed_origins"] = json!([format!("chrome-extension://{}/", extension_id)])
+        }
+    }
+
+    let path = dir.join(format!("{}.json", HOST_NAME));
+    std::fs::write(&path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn manifest_dir(browser: Browser) -> Option<PathBuf> {
+    let home = PathBuf::from(std::env::var_os("HOME")?);
+    if cfg!(target_os = "macos") {
+        let support = home.join("Library").join("Application Support");
+        return Some(match browser {
+            Browser::Chrome => support.join("Google/Chrome/NativeMessagingHosts"),
+            Browser::Chromium => support.join("Chromium/NativeMessagingHosts"),
+            Browser::Firefox => support.join("Mozilla/NativeMessagingHosts"),
+        });
+    }
+    if cfg!(target_os = "linux") {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".config"));
+        return Some(match browser {
+            Browser::Chrome => config.join("google-chrome/NativeMessagingHosts"),
+            Browser::Chromium => config.join("chromium/NativeMessagingHosts"),
+            Browser::Firefox => home.join(".mozilla/native-messaging-hosts"),
+        });
+    }
+    None
+}
@@ -0,0 +1,101 @@
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+
+use crate::format::Paste;
+
+/// Long enough for a remote cluster, short enough that `kubectl logs -f` doesn't hang the query.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The recent logs of a Docker container.
+pub fn docker_logs(
+    container: &str,
+    lines: usize,
+    since: Option<Duration>,
+    max_bytes: usize,
+) -> Result<Paste> {
+    let mut args = vec!["logs".to_string()];
+    match since {
+        Some(since) => args.extend(["--since".to_string(), format!("{}s", since.as_secs())]),
+        None => args.extend(["--tail".to_string(), lines.to_string()]),
+    }
+    args.push(container.to_string());
+    labelled("docker", &args, max_bytes)
+}
+
+/// The output of a kubectl command, given as one string like `"logs deploy/api --since=10m"`.
+pub fn kubectl(command: &str, max_bytes: usize) -> Result<Paste> {
+    let args: Vec<String> = command.split_whitespace().map(str::to_string).collect();
+    if args.is_empty() {
+        bail!("--kubectl needs a command, e.g. \"logs deploy/api --since=10m\"");
+    }
+    labelled("kubectl", &args, max_bytes)
+}
+
+/// Runs the command and labels its output with the command line, keeping the last `max_bytes`.
+fn labelled(program: &'static str, args: &[String], max_bytes: usize) -> Result<Paste> {
+    let command_line = format!("{} {}", program, args.join(" "));
+    let output = run(program, args).with_context(|| format!("Failed to run {}", command_line))?;
+
+    let mut output = output.trim_end();
+    if output.len() > max_bytes {
+        let mut start = output.len() - max_bytes;
+        while !output.is_char_boundary(start) {
+            start += 1;
+        }
+        output = &output[start..];
+        output = output.split_once('\n').map_or(output, |(_, rest)| rest);
+    }
+    Ok(Paste {
+        source: program,
+        content: format!("$ {}\n{}", command_line, output),
+    })
+}
+
+/// Stdout followed by stderr, since both tools pass a container's stderr through as their own.
+fn run(program: &str, args: &[String]) -> Result<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stdout = std::thread::spawn(move || {
+        let mut text = Vec::new();
+        let _ = stdout.read_to_end(&mut text);
+        text
+    });
+    let stderr = std::thread::spawn(move || {
+        let mut text = Vec::new();
+        let _ = stderr.read_to_end(&mut text);
+        text
+    });
+
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {}s", TIMEOUT.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    let stdout = String::from_utf8_lossy(&stdout.join().unwrap_or_default()).into_owned();
+    let stderr = String::from_utf8_lossy(&stderr.join().unwrap_or_default()).into_owned();
+
+    if !status.success() {
+        bail!("{}", stderr.trim());
+    }
+    Ok(match (stdout.trim().is_empty(), stderr.trim().is_empty()) {
+        (_, true) => stdout,
+        (true, false) => stderr,
+        (false, false) => format!("{}\n{}", stdout.trim_end(), stderr),
+    })
+}
@@ -0,0 +1,155 @@
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and NO_COLOR isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+static STDOUT_COLOR: AtomicBool = AtomicBool::new(false);
+static STDERR_COLOR: AtomicBool = AtomicBool::new(false);
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+const RED: &str = "1;31";
+const YELLOW: &str = "33";
+const GREEN: &str = "32";
+const BOLD: &str = "1";
+const DIM: &str = "2";
+
+/// Decides once whether stdout and stderr get colored output, and whether to report timings.
+pub fn init(choice: ColorChoice, verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let enabled = |terminal: bool| match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => terminal && !no_color,
+    };
+    STDOUT_COLOR.store(enabled(std::io::stdout().is_terminal()), Ordering::Relaxed);
+    STDERR_COLOR.store(enabled(std::io::stderr().is_terminal()), Ordering::Relaxed);
+}
+
+/// Holds back warnings, notes, and spinners, e.g. while a full-screen UI owns the terminal.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+fn paint(enabled: &AtomicBool, code: &str, text: &str) -> String {
+    if enabled.load(Ordering::Relaxed) {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn error(message: impl Display) {
+    eprintln!("{} {}", paint(&STDERR_COLOR, RED, "Error:"), message);
+}
+
+pub fn warn(message: impl Display) {
+    if quiet() {
+        return;
+    }
+    eprintln!("{}", paint(&STDERR_COLOR, YELLOW, &message.to_string()));
+}
+
+/// Progress and status messages, on stderr so they don't mix with piped output.
+pub fn note(message: impl Display) {
+    if quiet() {
+        return;
+    }
+    eprintln!("{}", message);
+}
+
+/// Prints a changed span of text as a removed and an added hunk.
+pub fn diff(line: usize, before: &str, after: &str) {
+    eprintln!(
+        "{}",
+        paint(&STDERR_COLOR, BOLD, &format!("@@ line {} @@", line))
+    );
+    for text in before.lines() {
+        eprintln!("{}", paint(&STDERR_COLOR, RED, &format!("-{}", text)));
+    }
+    for text in after.lines() {
+        eprintln!("{}", paint(&STDERR_COLOR, GREEN, &format!("+{}", text)));
+    }
+}
+
+/// Emphasis for headings in stdout tables.
+pub fn bold(text: &str) -> String {
+    paint(&STDOUT_COLOR, BOLD, text)
+}
+
+/// De-emphasis for quoted output on stdout.
+pub fn dim(text: &str) -> String {
+    paint(&STDOUT_COLOR, DIM, text)
+}
+
+const FRAMES: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// A spinner on stderr for a slow stage, cleared when dropped.
+pub struct Progress {
+    message: String,
+    start: Instant,
+    spinner: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+/// Starts a spinner if stderr is a terminal. With verbose output the elapsed time is printed
+/// when the stage ends.
+pub fn progress(message: impl Into<String>) -> Progress {
+    let message = message.into();
+    let spinner = (std::io::stderr().is_terminal() && !quiet()).then(|| {
+        let done = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let done = done.clone();
+            let message = message.clone();
+            std::thread::spawn(move || {
+                for frame in FRAMES.iter().cycle() {
+                    if done.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    eprint!("\r{} {}", frame, message);
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                eprint!("\r\x1b[2K");
+            })
+        };
+        (done, thread)
+    });
+    Progress {
+        message,
+        start: Instant::now(),
+        spinner,
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some((done, thread)) = self.spinner.take() {
+            done.store(true, Ordering::Relaxed);
+            let _ = thread.join();
+        }
+        if VERBOSE.load(Ordering::Relaxed) {
+            note(format!(
+                "{} ({:.1}s)",
+                self.message,
+                self.start.elapsed().as_secs_f64()
+            ));
+        }
+    }
+}
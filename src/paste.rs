@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+
+use crate::config::PasteConfig;
+use crate::output;
+
+const GIST_URL: &str = "https://api.github.com/gists";
+
+/// Uploads a prompt that won't fit in a URL and returns the link to it.
+pub fn upload(config: &PasteConfig, content: &str) -> Result<String> {
+    let token = std::env::var(config.token_env()).ok();
+    let progress = output::progress("Uploading context");
+
+    let url = match (&config.url, config.gist) {
+        (_, true) => GIST_URL,
+        (Some(url), false) => url.as_str(),
+        (None, false) => {
+            anyhow::bail!("Set paste.url or paste.gist in the config to upload context")
+        }
+    };
+    let mut request = ureq::post(url);
+    if let Some(token) = &token {
+        request = request.header("Authorization", &format!("Bearer {}", token));
+    }
+
+    let response = if config.gist {
+        if token.is_none() {
+            anyhow::bail!("Uploading a gist needs a token in ${}", config.token_env());
+        }
+        request
+            .header("Accept", "application/vnd.github+json")
+            .send_json(json!({
+                "description": "Context for a search prompt",
+                "public": false,
+                "files": { "context.md": { "content": content } },
+            }))
+    } else {
+        request
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .send(content)
+    };
+    let mut response = response.with_context(|| format!("Failed to upload context to {}", url))?;
+    drop(progress);
+
+    let link = match config.link_field() {
+        Some(field) => {
+            let body: serde_json::Value = response
+                .body_mut()
+                .read_json()
+                .context("Failed to parse paste service response")?;
+            body.get(field)
+                .and_then(|link| link.as_str())
+                .with_context(|| format!("Paste service response has no {:?} field", field))?
+                .to_string()
+        }
+        None => response
+            .body_mut()
+            .read_to_string()
+            .context("Failed to read paste service response")?
+            .trim()
+            .to_string(),
+    };
+    if !link.starts_with("http") {
+        anyhow::bail!("Paste service returned {:?} instead of a link", link);
+    }
+    Ok(link)
+}
+
+/// The prompt to send in place of one whose context was uploaded.
+pub fn prompt(prompt: &str, link: &str) -> String {
+    format!(
+        "{}\n\nThe context for this question was too long to include, so it is uploaded here:\n{}\n\
+Read it before answering.",
+        prompt, link
+    )
+}
@@ -0,0 +1,244 @@
+use crate::config::Config;
+use crate::context::{self, format_size};
+use crate::error::SearchError;
+use crate::format::{FormatOptions, Formatter, Order, Paste};
+use crate::provider::{Style, UrlProvider};
+use crate::source::{Collected, ContextSource};
+use crate::{lang, library, minify, output, rank, redact, repo, sanitize, summarize};
+
+/// Settings for turning a prompt and its context into a query.
+#[derive(Debug, Default, Clone)]
+pub struct ComposeOptions {
+    pub provider: String,
+    pub prompt: String,
+    /// Library template to wrap the prompt in
+    pub template: Option<String>,
+    /// Formatting style, instead of the provider's default
+    pub style: Option<Style>,
+    pub line_numbers: bool,
+    /// Prompt placement, instead of the configured one
+    pub order: Option<Order>,
+    /// Context section order by source name, instead of the configured one
+    pub source_order: Vec<String>,
+    /// Language to ask for the answer in
+    pub lang: Option<String>,
+    /// Country whose search results to prefer
+    pub region: Option<String>,
+    /// Search engine locale such as "de-DE", setting both language and country
+    pub locale: Option<String>,
+    pub minify: bool,
+    /// Maximum total bytes of file context
+    pub budget: Option<usize>,
+    /// Summarize trailing files with a local model to fit the budget
+    pub summarize: bool,
+    /// Skip sanitizing escape codes and Unicode
+    pub raw: bool,
+    pub no_redact: bool,
+    /// Start the context with the git repository, branch, and commit it came from
+    pub repo_header: bool,
+}
+
+pub struct Composed {
+    pub provider: UrlProvider,
+    pub prompt: String,
+    pub query: String,
+    pub sources: Vec<String>,
+    /// Bytes of each of `sources`, in the same order
+    pub source_bytes: Vec<usize>,
+    pub redacted: bool,
+    /// What redaction replaced, line by line
+    pub redactions: Vec<redact::Change>,
+}
+
+/// Composes a query with the provider's formatting style and the configured templates.
+pub fn compose(
+    options: &ComposeOptions,
+    sources: &[Box<dyn ContextSource>],
+    config: &Config,
+) -> Result<Composed, SearchError> {
+    let provider = UrlProvider::resolve(&options.provider, config)?;
+    let formatter = FormatOptions {
+        style: options.style.unwrap_or(provider.style),
+        line_numbers: options.line_numbers,
+        templates: config.templates(&options.provider),
+        order: options
+            .order
+            .or_else(|| config.order(&options.provider))
+            .unwrap_or_default(),
+        source_order: if options.source_order.is_empty() {
+            config.source_order(&options.provider)
+        } else {
+            options.source_order.clone()
+        },
+    };
+    compose_with(provider, options, sources, &formatter, config)
+}
+
+pub fn compose_with(
+    mut provider: UrlProvider,
+    options: &ComposeOptions,
+    sources: &[Box<dyn ContextSource>],
+    formatter: &dyn Formatter,
+    config: &Config,
+) -> Result<Composed, SearchError> {
+    let mut collected = Collected::default();
+    for source in sources {
+        source.collect(&mut collected, config)?;
+    }
+    let Collected {
+        mut pastes,
+        mut files,
+    } = collected;
+
+    let has_context = !pastes.is_empty() || !files.is_empty();
+    if options.repo_header
+        && has_context
+        && options.style.unwrap_or(provider.style) != Style::Keywords
+    {
+        let dir = std::env::current_dir().unwrap_or_default();
+        if let Some(header) = repo::header(&dir) {
+            pastes.insert(
+                0,
+                Paste {
+                    source: "repo",
+                    content: header,
+                },
+            );
+        }
+    }
+
+    let duplicates = context::dedup(&mut files);
+    if !duplicates.is_empty() {
+        let list: Vec<String> = duplicates
+            .iter()
+            .map(|(dropped, kept)| format!("{} (same as {})", dropped.display(), kept.display()))
+            .collect();
+        output::note(format!(
+            "Skipped {} duplicate file(s): {}",
+            duplicates.len(),
+            list.join(", ")
+        ));
+    }
+
+    if options.minify {
+        let before: usize = files.iter().map(|f| f.content.len()).sum();
+        for file in &mut files {
+            file.content = minify::minify(&file.content, file.lang);
+        }
+        let after: usize = files.iter().map(|f| f.content.len()).sum();
+        output::note(format!(
+            "Minified context from {} to {} (saved {})",
+            format_size(before),
+            format_size(after),
+            format_size(before.saturating_sub(after))
+        ));
+    }
+
+    if let Some(budget) = options.budget {
+        rank::by_relevance(&mut files, &options.prompt);
+        if options.summarize {
+            summarize::fit_to_budget(&mut files, budget, &config.summarize)?;
+        }
+        let omitted = context::apply_budget(&mut files, budget);
+        if !omitted.is_empty() {
+            let paths: Vec<String> = omitted
+                .iter()
+                .map(|f| f.path.display().to_string())
+                .collect();
+            output::warn(format!(
+                "Context budget reached, omitted: {}",
+                paths.join(", ")
+            ));
+        }
+    }
+
+    let mut prompt = options.prompt.clone();
+    if let Some(template) = &options.template {
+        prompt = library::apply_template(&config.library, template, &prompt)?;
+    }
+    let mut language_code = None;
+    if let Some(lang) = options.lang.as_deref().or(config.lang.as_deref()) {
+        let language = lang::resolve(lang);
+        if let Some(code) = &language.code {
+            provider.set_language(code);
+        }
+        language_code = language.code.clone();
+        // Search engines take keywords, so they only get the locale parameter.
+        if options.style.unwrap_or(provider.style) != Style::Keywords {
+            let instruction = lang::instruction(&language);
+            prompt = if prompt.is_empty() {
+                instruction
+            } else {
+                format!("{}\n\n{}", prompt, instruction)
+            };
+        }
+    }
+    let locale = options.locale.as_deref().or(config.locale.as_deref());
+    let (locale_language, locale_country) = match locale.map(|l| l.split_once(['-', '_'])) {
+        Some(Some((language, country))) => (Some(language), Some(country)),
+        Some(None) => (locale, None),
+        None => (None, None),
+    };
+    if let Some(language) = locale_language {
+        provider.set_language(&language.to_lowercase());
+    }
+    let region = options
+        .region
+        .as_deref()
+        .or(locale_country)
+        .or(config.region.as_deref());
+    if let Some(region) = region {
+        provider.set_region(region, locale_language.or(language_code.as_deref()));
+    }
+
+    let mut query = formatter.format(&pastes, &files, &prompt);
+
+    if let Some(provider) = config.provider(&options.provider) {
+        query = provider.wrap(&query);
+    }
+
+    if !options.raw {
+        query = sanitize::sanitize(&query);
+    }
+
+    let mut redacted = false;
+    let mut redactions = Vec::new();
+    if !options.no_redact {
+        let dir = std::env::current_dir().unwrap_or_default();
+        let redaction = redact::Redactor::new(&config.redact, &dir)?.redact(&query);
+        if !redaction.matches.is_empty() {
+            let mut kinds = redaction.matches.clone();
+            kinds.sort();
+            kinds.dedup();
+            output::warn(format!(
+                "Redacted {} secret(s) from the prompt: {}",
+                redaction.matches.len(),
+                kinds.join(", ")
+            ));
+            redacted = true;
+        }
+        query = redaction.text;
+        redactions = redaction.changes;
+    }
+
+    let (sources, source_bytes) = pastes
+        .iter()
+        .filter(|p| !p.content.is_empty())
+        .map(|p| (p.source.to_string(), p.content.len()))
+        .chain(
+            files
+                .iter()
+                .map(|f| (f.path.display().to_string(), f.content.len())),
+        )
+        .unzip();
+
+    Ok(Composed {
+        provider,
+        prompt,
+        query,
+        sources,
+        source_bytes,
+        redacted,
+        redactions,
+    })
+}
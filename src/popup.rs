@@ -0,0 +1,93 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+use crate::output;
+
+const CAPTURE_LINES: u32 = 200;
+const PREVIEW_LINES: usize = 15;
+
+/// Captures the current pane and reopens `search popup` inside a tmux popup to compose the query.
+pub fn open(provider: &str) -> Result<()> {
+    if std::env::var_os("TMUX").is_none() {
+        anyhow::bail!("search popup must be run inside tmux");
+    }
+
+    let output = Command::new("tmux")
+        .args(["capture-pane", "-p", "-J", "-S"])
+        .arg(format!("-{}", CAPTURE_LINES))
+        .output()
+        .context("Failed to run tmux capture-pane")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "tmux capture-pane failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let capture = std::env::temp_dir().join(format!("search-popup-{}.txt", std::process::id()));
+    std::fs::write(&capture, &output.stdout)
+        .with_context(|| format!("Failed to write {}", capture.display()))?;
+
+    let exe = std::env::current_exe().context("Failed to locate the search executable")?;
+    let inner = format!(
+        "{} -p {} popup --capture {}",
+        shell_quote(&exe.to_string_lossy()),
+        shell_quote(provider),
+        shell_quote(&capture.to_string_lossy())
+    );
+    let status = Command::new("tmux")
+        .args([
+            "display-popup",
+            "-E",
+            "-w",
+            "80%",
+            "-h",
+            "60%",
+            "-T",
+            " search ",
+        ])
+        .arg(inner)
+        .status()
+        .context("Failed to run tmux display-popup");
+    let _ = std::fs::remove_file(&capture);
+    if !status?.success() {
+        anyhow::bail!("tmux display-popup failed");
+    }
+    Ok(())
+}
+
+/// Shows the tail of the captured pane and reads a prompt, returning `None` if left empty.
+pub fn compose(capture: &Path) -> Result<Option<(String, String)>> {
+    let text = std::fs::read_to_string(capture)
+        .with_context(|| format!("Failed to read {}", capture.display()))?;
+    let text = text.trim_end().to_string();
+
+    let lines: Vec<&str> = text.lines().collect();
+    let start = lines.len().saturating_sub(PREVIEW_LINES);
+    println!("{}", output::dim(&lines[start..].join("\n")));
+    println!();
+    print!("Ask about this output (empty to cancel): ");
+    std::io::stdout().flush()?;
+
+    let mut prompt = String::new();
+    std::io::stdin().lock().read_line(&mut prompt)?;
+    let prompt = prompt.trim().to_string();
+    if prompt.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some((text, prompt)))
+}
+
+/// Keeps the popup open long enough to read an error.
+pub fn pause_on_error(error: &anyhow::Error) {
+    output::error(format!("{:#}", error));
+    eprint!("Press enter to close");
+    let _ = std::io::stdin().lock().read_line(&mut String::new());
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
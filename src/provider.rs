@@ -0,0 +1,384 @@
+use clap::ValueEnum;
+use percent_encoding::{
+    percent_encode_byte, utf8_percent_encode, AsciiSet, CONTROLS, NON_ALPHANUMERIC,
+};
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::error::SearchError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    /// XML-tagged context blocks
+    Xml,
+    /// Markdown code fences
+    Markdown,
+    /// Plain keywords on a single line
+    Keywords,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// Escape only characters that are unsafe in a query string, spaces as `+`
+    #[default]
+    Query,
+    /// application/x-www-form-urlencoded
+    Form,
+    /// Escape everything but letters and digits
+    Strict,
+}
+
+const QUERY_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'\'')
+    .add(b'+')
+    .add(b'<')
+    .add(b'=')
+    .add(b'>')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+const FORM_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'*')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_');
+
+impl Encoding {
+    pub fn encode(self, text: &str, extra: &str) -> String {
+        let base = match self {
+            Encoding::Query => QUERY_SET,
+            Encoding::Form => FORM_SET,
+            Encoding::Strict => NON_ALPHANUMERIC,
+        };
+        // Escaping the extra characters in the same pass leaves existing escapes alone.
+        let mut encoded = String::with_capacity(text.len());
+        for c in text.chars() {
+            if c.is_ascii() && extra.contains(c) {
+                encoded.push_str(percent_encode_byte(c as u8));
+            } else if c == ' ' && self != Encoding::Strict {
+                encoded.push('+');
+            } else {
+                encoded.extend(utf8_percent_encode(c.encode_utf8(&mut [0; 4]), base));
+            }
+        }
+        encoded
+    }
+}
+
+/// A chat or search service that a composed query can be sent to.
+pub trait Provider {
+    fn name(&self) -> &str;
+    /// Page to open when the query doesn't fit in a URL.
+    fn home(&self) -> &str;
+    fn style(&self) -> Style;
+    fn max_url_length(&self) -> usize;
+    fn query_url(&self, query: &str) -> String;
+
+    /// Whether the query URL carries the prompt, rather than just opening a page to paste it into.
+    fn takes_query(&self) -> bool {
+        true
+    }
+
+    /// CSS selector of the chat input, for typing the prompt through WebDriver.
+    fn input_selector(&self) -> Option<&str> {
+        None
+    }
+
+    /// Name of the provider's desktop app.
+    fn app(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// A builtin or configured provider that takes the query in a URL template.
+#[derive(Debug, Clone)]
+pub struct UrlProvider {
+    pub name: String,
+    pub url: String,
+    pub home: String,
+    pub style: Style,
+    pub max_url_length: usize,
+    pub encoding: Encoding,
+    pub encode_chars: String,
+    pub input_selector: Option<String>,
+    pub app: Option<String>,
+    pub lang_param: Option<String>,
+    pub region_param: Option<String>,
+    /// Conversation URL template with an `{id}` placeholder
+    pub chat_url: Option<String>,
+    /// Extra query parameters appended to the query URL
+    pub params: Vec<(String, String)>,
+}
+
+struct Builtin {
+    name: &'static str,
+    url: &'static str,
+    home: &'static str,
+    style: Style,
+    max_url_length: usize,
+    input_selector: &'static str,
+    app: Option<&'static str>,
+    lang_param: Option<&'static str>,
+    region_param: Option<&'static str>,
+    chat_url: Option<&'static str>,
+}
+
+const BUILTIN: &[Builtin] = &[
+    Builtin {
+        name: "chatgpt",
+        url: "https://chatgpt.com/?q={query}",
+        home: "https://chatgpt.com/",
+        style: Style::Markdown,
+        max_url_length: 8_000,
+        input_selector: "#prompt-textarea",
+        app: Some("ChatGPT"),
+        lang_param: None,
+        region_param: None,
+        chat_url: Some("https://chatgpt.com/c/{id}"),
+    },
+    Builtin {
+        name: "claude",
+        url: "https://claude.ai/new?q={query}",
+        home: "https://claude.ai/new",
+        style: Style::Xml,
+        max_url_length: 14_000,
+        input_selector: "div[contenteditable='true']",
+        app: Some("Claude"),
+        lang_param: None,
+        region_param: None,
+        chat_url: Some("https://claude.ai/chat/{id}"),
+    },
+    Builtin {
+        name: "perplexity",
+        url: "https://www.perplexity.ai/search?q={query}",
+        home: "https://www.perplexity.ai/",
+        style: Style::Markdown,
+        max_url_length: 8_000,
+        input_selector: "#ask-input, textarea",
+        app: None,
+        lang_param: None,
+        region_param: None,
+        chat_url: Some("https://www.perplexity.ai/search/{id}"),
+    },
+    Builtin {
+        name: "google",
+        url: "https://www.google.com/search?q={query}",
+        home: "https://www.google.com/",
+        style: Style::Keywords,
+        max_url_length: 2_000,
+        input_selector: "textarea[name='q']",
+        app: None,
+        lang_param: Some("hl"),
+        region_param: Some("gl"),
+        chat_url: None,
+    },
+    Builtin {
+        name: "duckduckgo",
+        url: "https://duckduckgo.com/?q={query}",
+        home: "https://duckduckgo.com/",
+        style: Style::Keywords,
+        max_url_length: 2_000,
+        input_selector: "input[name='q']",
+        app: None,
+        lang_param: None,
+        region_param: Some("kl"),
+        chat_url: None,
+    },
+    Builtin {
+        name: "bing",
+        url: "https://www.bing.com/search?q={query}",
+        home: "https://www.bing.com/",
+        style: Style::Keywords,
+        max_url_length: 2_000,
+        input_selector: "textarea[name='q'], input[name='q']",
+        app: None,
+        lang_param: Some("setlang"),
+        region_param: Some("cc"),
+        chat_url: None,
+    },
+];
+
+pub const DEFAULT_MAX_URL_LENGTH: usize = 8_000;
+
+pub fn is_builtin(name: &str) -> bool {
+    BUILTIN.iter().any(|b| b.name == name)
+}
+
+/// Builtin providers followed by the configured ones.
+pub fn names(config: &Config) -> Vec<String> {
+    let mut custom: Vec<&String> = config
+        .providers
+        .keys()
+        .filter(|name| !is_builtin(name))
+        .collect();
+    custom.sort();
+    BUILTIN
+        .iter()
+        .map(|b| b.name.to_string())
+        .chain(custom.into_iter().cloned())
+        .collect()
+}
+
+impl UrlProvider {
+    pub fn resolve(name: &str, config: &Config) -> Result<Self, SearchError> {
+        let builtin = BUILTIN.iter().find(|b| b.name == name);
+        let custom = config.provider(name);
+
+        let url = custom
+            .and_then(|c| c.url.clone())
+            .or_else(|| builtin.map(|b| b.url.to_string()));
+        let Some(url) = url else {
+            return Err(SearchError::UnknownProvider(name.to_string()));
+        };
+        let home = custom
+            .and_then(|c| c.home.clone())
+            .or_else(|| builtin.map(|b| b.home.to_string()))
+            .or_else(|| origin(&url))
+            .unwrap_or_else(|| url.replace("{query}", ""));
+        let style = custom
+            .and_then(|c| c.style)
+            .or_else(|| builtin.map(|b| b.style))
+            .unwrap_or(Style::Markdown);
+        let max_url_length = custom
+            .and_then(|c| c.max_url_length)
+            .or_else(|| builtin.map(|b| b.max_url_length))
+            .unwrap_or(DEFAULT_MAX_URL_LENGTH)
+            .min(config.max_url_length.unwrap_or(usize::MAX));
+
+        Ok(Self {
+            name: name.to_string(),
+            url,
+            home,
+            style,
+            max_url_length,
+            encoding: custom.and_then(|c| c.encoding).unwrap_or_default(),
+            encode_chars: custom
+                .and_then(|c| c.encode_chars.clone())
+                .unwrap_or_default(),
+            input_selector: custom
+                .and_then(|c| c.input_selector.clone())
+                .or_else(|| builtin.map(|b| b.input_selector.to_string())),
+            app: custom
+                .and_then(|c| c.app.clone())
+                .or_else(|| builtin.and_then(|b| b.app.map(str::to_string))),
+            lang_param: custom
+                .and_then(|c| c.lang_param.clone())
+                .or_else(|| builtin.and_then(|b| b.lang_param.map(str::to_string))),
+            region_param: custom
+                .and_then(|c| c.region_param.clone())
+                .or_else(|| builtin.and_then(|b| b.region_param.map(str::to_string))),
+            chat_url: custom
+                .and_then(|c| c.chat_url.clone())
+                .or_else(|| builtin.and_then(|b| b.chat_url.map(str::to_string))),
+            params: Vec::new(),
+        })
+    }
+
+    /// Sets the provider's interface language, if it takes one in the URL.
+    pub fn set_language(&mut self, code: &str) {
+        if let Some(param) = self.lang_param.clone() {
+            self.set_param(&param, code);
+        }
+    }
+
+    /// Sets the country of search results, if the provider takes one in the URL.
+    ///
+    /// DuckDuckGo's `kl` combines country and language, so it's only set when the language is known.
+    pub fn set_region(&mut self, country: &str, language: Option<&str>) {
+        let Some(param) = self.region_param.clone() else {
+            return;
+        };
+        let country = country.to_lowercase();
+        if param == "kl" {
+            if let Some(language) = language {
+                self.set_param(&param, &format!("{}-{}", country, language.to_lowercase()));
+            }
+        } else {
+            self.set_param(&param, &country);
+        }
+    }
+
+    /// Sends the query to an existing conversation, given by its URL or its id.
+    ///
+    /// Unless the conversation URL template has a `{query}`, the prompt is pasted instead.
+    pub fn open_chat(&mut self, chat: &str) -> Result<(), SearchError> {
+        let url = if chat.starts_with("https://") || chat.starts_with("http://") {
+            chat.to_string()
+        } else {
+            let template = self
+                .chat_url
+                .as_ref()
+                .ok_or_else(|| SearchError::NoChatUrl(self.name.clone()))?;
+            template.replace("{id}", &Encoding::Form.encode(chat, ""))
+        };
+        self.home = url.replace("{query}", "");
+        self.home = self.home.trim_end_matches(['?', '&']).to_string();
+        self.url = url;
+        Ok(())
+    }
+
+    fn set_param(&mut self, key: &str, value: &str) {
+        self.params.retain(|(k, _)| k != key);
+        self.params.push((key.to_string(), value.to_string()));
+    }
+}
+
+impl Provider for UrlProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn home(&self) -> &str {
+        &self.home
+    }
+
+    fn style(&self) -> Style {
+        self.style
+    }
+
+    fn max_url_length(&self) -> usize {
+        self.max_url_length
+    }
+
+    fn query_url(&self, query: &str) -> String {
+        let encoded_query = self.encoding.encode(query, &self.encode_chars);
+        let mut url = self.url.replace("{query}", &encoded_query);
+        for (key, value) in &self.params {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(&Encoding::Form.encode(key, ""));
+            url.push('=');
+            url.push_str(&Encoding::Form.encode(value, ""));
+        }
+        url
+    }
+
+    fn takes_query(&self) -> bool {
+        self.url.contains("{query}")
+    }
+
+    fn input_selector(&self) -> Option<&str> {
+        self.input_selector.as_deref()
+    }
+
+    fn app(&self) -> Option<&str> {
+        self.app.as_deref()
+    }
+}
+
+fn origin(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(&url.replace("{query}", "")).ok()?;
+    Some(format!("{}/", parsed.origin().ascii_serialization()))
+}
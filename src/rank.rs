@@ -0,0 +1,83 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::context::ContextFile;
+
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+/// A term in the file path says more about the file than one in its body.
+const PATH_WEIGHT: usize = 3;
+
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from", "how",
+    "i", "in", "is", "it", "me", "my", "of", "on", "or", "should", "that", "the", "this", "to",
+    "what", "when", "where", "which", "why", "with", "you",
+];
+
+/// Orders files by BM25 relevance to the prompt, most relevant first. Files that share no
+/// terms with the prompt keep their relative order at the end.
+pub fn by_relevance(files: &mut Vec<ContextFile>, prompt: &str) {
+    let query: HashSet<String> = terms(prompt).collect();
+    if query.is_empty() || files.len() < 2 {
+        return;
+    }
+
+    let documents: Vec<HashMap<String, usize>> = files.iter().map(term_counts).collect();
+    let lengths: Vec<usize> = documents.iter().map(|d| d.values().sum()).collect();
+    let average = lengths.iter().sum::<usize>() as f64 / files.len() as f64;
+    let n = files.len() as f64;
+
+    let scores: Vec<f64> = documents
+        .iter()
+        .zip(&lengths)
+        .map(|(document, &length)| {
+            query
+                .iter()
+                .filter_map(|term| {
+                    let tf = *document.get(term)? as f64;
+                    let df = documents.iter().filter(|d| d.contains_key(term)).count() as f64;
+                    let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+                    let norm = 1.0 - B + B * length as f64 / average.max(1.0);
+                    Some(idf * tf * (K1 + 1.0) / (tf + K1 * norm))
+                })
+                .sum()
+        })
+        .collect();
+
+    let mut scored: Vec<(f64, ContextFile)> = scores.into_iter().zip(files.drain(..)).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    files.extend(scored.into_iter().map(|(_, file)| file));
+}
+
+fn term_counts(file: &ContextFile) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for term in terms(&file.content) {
+        *counts.entry(term).or_insert(0) += 1;
+    }
+    for term in terms(&file.path.to_string_lossy()) {
+        *counts.entry(term).or_insert(0) += PATH_WEIGHT;
+    }
+    counts
+}
+
+/// Lowercased words and identifier parts, so `parse_config` and `parseConfig` both match "config".
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .flat_map(split_camel_case)
+        .map(|word| word.to_lowercase())
+        .filter(|word| word.len() > 1 && !STOPWORDS.contains(&word.as_str()))
+}
+
+fn split_camel_case(word: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    for pair in chars.windows(2) {
+        let [(_, a), (i, b)] = pair else { continue };
+        if a.is_lowercase() && b.is_uppercase() {
+            parts.push(&word[start..*i]);
+            start = *i;
+        }
+    }
+    parts.push(&word[start..]);
+    parts
+}
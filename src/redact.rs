@@ -0,0 +1,227 @@
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+
+use crate::config::RedactConfig;
+
+struct Rule {
+    name: &'static str,
+    pattern: &'static str,
+}
+
+// Rules with a `secret` group only replace that group, keeping the surrounding key name.
+const RULES: &[Rule] = &[
+    Rule {
+        name: "private key",
+        pattern: r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+    },
+    Rule {
+        name: "AWS access key",
+        pattern: r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+    },
+    Rule {
+        name: "GitHub token",
+        pattern: r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{40,})\b",
+    },
+    Rule {
+        name: "API key",
+        pattern: r"\bsk-[A-Za-z0-9_-]{20,}",
+    },
+    Rule {
+        name: "Slack token",
+        pattern: r"\bxox[abprs]-[A-Za-z0-9-]{10,}",
+    },
+    Rule {
+        name: "JWT",
+        pattern: r"\beyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}",
+    },
+    Rule {
+        name: "credential",
+        pattern: r#"(?i)\b[a-z0-9_]*(?:password|passwd|secret|token|api_?key)[a-z0-9_]*["']?\s*[:=]\s*["']?(?P<secret>[^\s"'\[][^\s"']{7,})"#,
+    },
+];
+
+pub struct Redaction {
+    pub text: String,
+    pub matches: Vec<String>,
+    pub changes: Vec<Change>,
+}
+
+/// Lines of the text before and after redaction, for reviewing what was replaced.
+pub struct Change {
+    /// First line of the change, counting from 1
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// A secret found in the text, or a match that an allowlist pattern let through.
+pub struct Finding {
+    pub rule: String,
+    /// Byte range that gets replaced, which is only the value for key-value rules
+    pub range: Range<usize>,
+    pub allowed_by: Option<String>,
+}
+
+/// The built-in rules plus the configured ones, minus those disabled for the current project.
+pub struct Redactor {
+    rules: Vec<(String, Regex)>,
+    allow: Vec<(String, Regex)>,
+}
+
+impl Redactor {
+    pub fn new(config: &RedactConfig, dir: &Path) -> Result<Self> {
+        let mut layers = vec![config];
+        let mut projects: Vec<_> = config
+            .projects
+            .iter()
+            .filter(|(root, _)| dir.starts_with(root) || dir.starts_with(expand(root)))
+            .collect();
+        // Deeper project directories override shallower ones.
+        projects.sort_by_key(|(root, _)| root.components().count());
+        layers.extend(projects.into_iter().map(|(_, project)| project));
+
+        let disabled: Vec<&str> = layers
+            .iter()
+            .flat_map(|layer| layer.disable.iter().map(String::as_str))
+            .collect();
+        let mut rules = Vec::new();
+        for rule in RULES.iter().filter(|rule| !disabled.contains(&rule.name)) {
+            let regex = Regex::new(rule.pattern).expect("built-in redaction rule is valid");
+            rules.push((rule.name.to_string(), regex));
+        }
+        let mut allow = Vec::new();
+        for layer in layers {
+            rules.extend(compile(&layer.rules, "redaction rule")?);
+            allow.extend(compile(&layer.allow, "redaction allowlist pattern")?);
+        }
+        rules.retain(|(name, _)| !disabled.contains(&name.as_str()));
+
+        Ok(Self { rules, allow })
+    }
+
+    /// Finds secrets in rule order; a match overlapping an earlier one is skipped.
+    pub fn find(&self, text: &str) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = Vec::new();
+
+        for (name, regex) in &self.rules {
+            for caps in regex.captures_iter(text) {
+                let whole = caps.get(0).unwrap();
+                let secret = caps.name("secret").unwrap_or(whole);
+                let range = secret.range();
+                if findings
+                    .iter()
+                    .any(|f| f.range.start < range.end && range.start < f.range.end)
+                {
+                    continue;
+                }
+                let allowed_by = self
+                    .allow
+                    .iter()
+                    .find(|(_, allow)| {
+                        allow.is_match(secret.as_str()) || allow.is_match(whole.as_str())
+                    })
+                    .map(|(name, _)| name.clone());
+                findings.push(Finding {
+                    rule: name.clone(),
+                    range,
+                    allowed_by,
+                });
+            }
+        }
+
+        findings.sort_by_key(|f| f.range.start);
+        findings
+    }
+
+    pub fn redact(&self, text: &str) -> Redaction {
+        let findings = self.find(text);
+        let mut out = String::with_capacity(text.len());
+        let mut matches = Vec::new();
+        let mut last = 0;
+
+        let secrets: Vec<Finding> = findings
+            .into_iter()
+            .filter(|f| f.allowed_by.is_none())
+            .collect();
+        for finding in &secrets {
+            out.push_str(&text[last..finding.range.start]);
+            out.push_str(&placeholder(&finding.rule));
+            last = finding.range.end;
+            matches.push(finding.rule.clone());
+        }
+        out.push_str(&text[last..]);
+
+        Redaction {
+            text: out,
+            matches,
+            changes: changes(text, &secrets),
+        }
+    }
+}
+
+fn placeholder(rule: &str) -> String {
+    format!("[REDACTED {}]", rule)
+}
+
+/// Groups the findings by the lines they touch.
+fn changes(text: &str, secrets: &[Finding]) -> Vec<Change> {
+    let mut spans: Vec<(Range<usize>, Vec<&Finding>)> = Vec::new();
+    for finding in secrets {
+        let start = text[..finding.range.start].rfind('\n').map_or(0, |i| i + 1);
+        let end = text[finding.range.end..]
+            .find('\n')
+            .map_or(text.len(), |i| finding.range.end + i);
+        match spans.last_mut() {
+            Some((span, group)) if start <= span.end => {
+                span.end = span.end.max(end);
+                group.push(finding);
+            }
+            _ => spans.push((start..end, vec![finding])),
+        }
+    }
+
+    spans
+        .into_iter()
+        .map(|(span, group)| {
+            let mut after = String::new();
+            let mut last = span.start;
+            for finding in group {
+                after.push_str(&text[last..finding.range.start]);
+                after.push_str(&placeholder(&finding.rule));
+                last = finding.range.end;
+            }
+            after.push_str(&text[last..span.end]);
+            Change {
+                line: text[..span.start].matches('\n').count() + 1,
+                before: text[span].to_string(),
+                after,
+            }
+        })
+        .collect()
+}
+
+fn compile(
+    patterns: &std::collections::HashMap<String, String>,
+    what: &str,
+) -> Result<Vec<(String, Regex)>> {
+    let mut names: Vec<_> = patterns.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| {
+            let regex = Regex::new(&patterns[name])
+                .with_context(|| format!("Invalid {} {:?}", what, name))?;
+            Ok((name.clone(), regex))
+        })
+        .collect()
+}
+
+fn expand(path: &Path) -> std::path::PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
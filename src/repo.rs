@@ -0,0 +1,36 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A line naming the git repository, branch, and commit the context came from, or None outside
+/// a repository.
+pub fn header(dir: &Path) -> Option<String> {
+    let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+    let name = Path::new(&root).file_name()?.to_string_lossy().into_owned();
+    let mut details = Vec::new();
+    match git(dir, &["symbolic-ref", "--short", "-q", "HEAD"]) {
+        Some(branch) => details.push(format!("branch {}", branch)),
+        None => details.push("detached HEAD".to_string()),
+    }
+    // A fresh repository has no commits yet.
+    if let Some(commit) = git(dir, &["rev-parse", "--short", "HEAD"]) {
+        details.push(format!("commit {}", commit));
+    }
+    if git(dir, &["status", "--porcelain", "--untracked-files=no"]).is_some() {
+        details.push("with uncommitted changes".to_string());
+    }
+    Some(format!("Repository: {} ({})", name, details.join(", ")))
+}
+
+/// Trimmed stdout of a successful git command, None if it failed or printed nothing.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !text.is_empty()).then_some(text)
+}
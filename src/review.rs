@@ -0,0 +1,35 @@
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+pub const PROMPT: &str = "Review this change as an experienced maintainer. Point out bugs, \
+missed edge cases, security problems, and unclear code. Group findings by file, cite the \
+relevant lines as path:line, and order them by severity. Skip praise and style nits.";
+
+/// The diff to review: a pull request via `gh`, the range from `base`, or the staged changes.
+pub fn diff(pr: Option<&str>, base: Option<&str>) -> Result<String> {
+    let (program, args): (&str, Vec<String>) = match (pr, base) {
+        (Some(pr), _) => ("gh", vec!["pr".into(), "diff".into(), pr.into()]),
+        (None, Some(base)) => ("git", vec!["diff".into(), format!("{}...HEAD", base)]),
+        (None, None) => ("git", vec!["diff".into(), "--cached".into()]),
+    };
+
+    let output = Command::new(program)
+        .args(&args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let diff = String::from_utf8(output.stdout).context("Diff is not valid UTF-8")?;
+    if diff.trim().is_empty() {
+        anyhow::bail!("Nothing to review; stage changes or pass --base or --pr");
+    }
+    Ok(diff)
+}
@@ -0,0 +1,122 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{self, HistoryConfig};
+use crate::history;
+
+/// Captures kept, newest first.
+pub const SIZE: usize = 10;
+
+/// Clipboard contents read by --clipboard, kept so earlier context can be reused.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Capture {
+    pub timestamp: DateTime<Utc>,
+    pub content: String,
+}
+
+impl Capture {
+    pub fn local_time(&self) -> String {
+        self.timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    pub fn summary(&self) -> String {
+        let first = self.content.trim().lines().next().unwrap_or_default();
+        let mut line: String = first.chars().take(72).collect();
+        if first.chars().count() > 72 || self.content.trim().lines().count() > 1 {
+            line.push('…');
+        }
+        line
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("clipboard.json"))
+}
+
+/// Recent captures, newest first.
+pub fn load(config: &HistoryConfig) -> Result<Vec<Capture>> {
+    let Some(path) = path() else {
+        return Ok(Vec::new());
+    };
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read clipboard history {}", path.display()))?;
+    if !text.trim_start().starts_with('[') {
+        text = history::unseal(config, text.trim())?;
+    }
+    serde_json::from_str(&text)
+        .with_context(|| format!("Failed to parse clipboard history {}", path.display()))
+}
+
+/// Records a capture, moving it to the front if it was captured before.
+pub fn push(config: &HistoryConfig, content: &str) -> Result<()> {
+    let mut captures = load(config)?;
+    captures.retain(|c| c.content != content);
+    captures.insert(
+        0,
+        Capture {
+            timestamp: Utc::now(),
+            content: content.to_string(),
+        },
+    );
+    captures.truncate(SIZE);
+    save(config, &captures)
+}
+
+/// Drops captures from before the given time, returning how many were removed.
+pub fn purge(config: &HistoryConfig, before: DateTime<Utc>) -> Result<usize> {
+    let mut captures = load(config)?;
+    let count = captures.len();
+    captures.retain(|c| c.timestamp >= before);
+    let removed = count - captures.len();
+    if removed > 0 {
+        save(config, &captures)?;
+    }
+    Ok(removed)
+}
+
+fn save(config: &HistoryConfig, captures: &[Capture]) -> Result<()> {
+    let path = path().context("Could not determine a data directory for clipboard history")?;
+    let mut text = serde_json::to_string(captures)?;
+    if config.encrypt {
+        text = history::seal(config, &text)?;
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .with_context(|| format!("Failed to write clipboard history {}", path.display()))
+}
+
+/// The `n`th most recent capture, counting from 1.
+pub fn get(config: &HistoryConfig, n: usize) -> Result<Capture> {
+    let captures = load(config)?;
+    if n == 0 || n > captures.len() {
+        bail!(
+            "There are {} clipboard captures; run `search clips` to list them",
+            captures.len()
+        );
+    }
+    Ok(captures[n - 1].clone())
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use url::Url;
+
+use crate::browser::{self, BrowserOptions};
+use crate::manifest;
+
+pub struct Request {
+    pub arguments: Value,
+    pub success: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Parses `search://query?p=claude&q=...&x=src/main.rs` into tool-style arguments.
+pub fn parse(link: &str) -> Result<Request> {
+    let url = Url::parse(link).with_context(|| format!("Invalid URL {:?}", link))?;
+    if url.scheme() != "search" {
+        anyhow::bail!("Expected a search:// URL, got {}://", url.scheme());
+    }
+    match url.host_str().unwrap_or("") {
+        "" | "query" => {}
+        other => anyhow::bail!("Unknown action search://{}", other),
+    }
+
+    let mut arguments = Map::new();
+    let mut context = Vec::new();
+    let mut success = None;
+    let mut error = None;
+    for (key, value) in url.query_pairs() {
+        let value = value.into_owned();
+        match key.as_ref() {
+            "q" | "prompt" => {
+                arguments.insert("prompt".into(), json!(value));
+            }
+            "p" | "provider" => {
+                arguments.insert("provider".into(), json!(value));
+            }
+            "x" | "context" => context.push(json!(value)),
+            "style" | "template" => {
+                arguments.insert(key.into_owned(), json!(value));
+            }
+            "manifest" | "minify" | "line_numbers" => {
+                arguments.insert(key.into_owned(), json!(value != "0" && value != "false"));
+            }
+            "budget" => {
+                let budget: u64 = value.parse().context("budget must be a number")?;
+                arguments.insert("budget".into(), json!(budget));
+            }
+            "x-success" => success = Some(value),
+            "x-error" => error = Some(value),
+            _ => {}
+        }
+    }
+    if !context.is_empty() {
+        arguments.insert("context".into(), Value::Array(context));
+    }
+
+    Ok(Request {
+        arguments: Value::Object(arguments),
+        success,
+        error,
+    })
+}
+
+/// Resolves the files a link asks to include, as context or with `manifest`, which must be under
+/// `dir`.
+///
+/// Links can come from any web page, so they can't reach files elsewhere, like `~/.ssh`.
+pub fn confine_context(request: &mut Request, dir: &Path) -> Result<Vec<PathBuf>> {
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", dir.display()))?;
+    let inside = |path: PathBuf| {
+        if !path.starts_with(&dir) {
+            anyhow::bail!(
+                "The link asks for {}, which is outside {}",
+                path.display(),
+                dir.display()
+            );
+        }
+        Ok(path)
+    };
+
+    let mut paths = Vec::new();
+    if let Some(context) = request.arguments.get_mut("context") {
+        for value in context.as_array_mut().into_iter().flatten() {
+            let given = value.as_str().unwrap_or_default();
+            let path = dir
+                .join(given)
+                .canonicalize()
+                .with_context(|| format!("Failed to resolve {:?} from the link", given))?;
+            let path = inside(path)?;
+            *value = json!(path.display().to_string());
+            paths.push(path);
+        }
+    }
+    if request.arguments["manifest"] == json!(true) {
+        for path in manifest::files(&dir) {
+            paths.push(inside(path)?);
+        }
+    }
+    Ok(paths)
+}
+
+/// Opens an x-callback-url with extra query parameters.
+pub fn callback(target: &str, params: &[(&str, &str)]) -> Result<()> {
+    let mut url =
+        Url::parse(target).with_context(|| format!("Invalid callback URL {:?}", target))?;
+    url.query_pairs_mut().extend_pairs(params);
+    browser::open(url.as_str(), &BrowserOptions::default()).context("Failed to open callback URL")
+}
+
+/// Registers `search handle-url` as the handler for `search://` links.
+pub fn register() -> Result<PathBuf> {
+    if !cfg!(target_os = "linux") {
+        anyhow::bail!(
+            "Registering search:// is only automated on Linux; on macOS, wrap `search handle-url` in an app declaring CFBundleURLTypes"
+        );
+    }
+
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        })
+        .context("Could not determine the applications directory")?;
+    let dir = base.join("applications");
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let exe = std::env::current_exe().context("Failed to locate the search executable")?;
+    let path = dir.join("search-url-handler.desktop");
+    std::fs::write(
+        &path,
+        format!(
+            "[Desktop Entry]\nType=Application\nName=search\nExec=\"{}\" handle-url %u\nNoDisplay=true\nMimeType=x-scheme-handler/search;\n",
+            exe.display()
+        ),
+    )
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let status = Command::new("xdg-mime")
+        .args([
+            "default",
+            "search-url-handler.desktop",
+            "x-scheme-handler/search",
+        ])
+        .status()
+        .context("Failed to run xdg-mime")?;
+    if !status.success() {
+        anyhow::bail!("xdg-mime failed to register the search:// handler");
+    }
+    Ok(path)
+}
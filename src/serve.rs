@@ -0,0 +1,107 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::output;
+
+/// Serves `POST /query` on localhost, passing the JSON body to `handle`.
+pub fn serve(
+    port: u16,
+    allowed_origins: &[String],
+    mut handle: impl FnMut(&Value) -> Result<Value>,
+) -> Result<()> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| anyhow::anyhow!("Failed to listen on port {}: {}", port, e))?;
+    output::note(format!("Listening on http://127.0.0.1:{}", port));
+
+    for mut request in server.incoming_requests() {
+        let origin = header(&request, "Origin");
+        if let Some(origin) = &origin {
+            if !allowed_origins.iter().any(|allowed| allowed == origin) {
+                respond(request, 403, json!({ "error": "Origin not allowed" }), None);
+                continue;
+            }
+        }
+
+        if *request.method() == Method::Options {
+            respond(request, 204, Value::Null, origin.as_deref());
+            continue;
+        }
+        if request.url() != "/query" {
+            respond(
+                request,
+                404,
+                json!({ "error": "Not found" }),
+                origin.as_deref(),
+            );
+            continue;
+        }
+        if *request.method() != Method::Post {
+            respond(
+                request,
+                405,
+                json!({ "error": "Use POST" }),
+                origin.as_deref(),
+            );
+            continue;
+        }
+        // Requiring JSON forces a CORS preflight, so other sites can't drive the server.
+        let is_json = header(&request, "Content-Type")
+            .is_some_and(|value| value.starts_with("application/json"));
+        if !is_json {
+            let body = json!({ "error": "Content-Type must be application/json" });
+            respond(request, 415, body, origin.as_deref());
+            continue;
+        }
+
+        let mut body = String::new();
+        let (status, value) = match request.as_reader().read_to_string(&mut body) {
+            Err(e) => (
+                400,
+                json!({ "error": format!("Failed to read body: {}", e) }),
+            ),
+            Ok(_) => match serde_json::from_str::<Value>(&body) {
+                Err(e) => (400, json!({ "error": format!("Invalid JSON: {}", e) })),
+                Ok(input) => match handle(&input) {
+                    Ok(output) => (200, output),
+                    Err(e) => (400, json!({ "error": format!("{:#}", e) })),
+                },
+            },
+        };
+        respond(request, status, value, origin.as_deref());
+    }
+
+    Ok(())
+}
+
+fn header(request: &Request, name: &str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|h| h.value.to_string())
+}
+
+fn respond(request: Request, status: u16, body: Value, origin: Option<&str>) {
+    let mut headers = vec![Header::from_bytes("Content-Type", "application/json").unwrap()];
+    if let Some(origin) = origin {
+        headers.extend([
+            Header::from_bytes("Access-Control-Allow-Origin", origin).unwrap(),
+            Header::from_bytes("Access-Control-Allow-Methods", "POST, OPTIONS").unwrap(),
+            Header::from_bytes("Access-Control-Allow-Headers", "Content-Type").unwrap(),
+            Header::from_bytes("Vary", "Origin").unwrap(),
+        ]);
+    }
+    let body = if body.is_null() {
+        String::new()
+    } else {
+        body.to_string()
+    };
+    let mut response = Response::from_string(body).with_status_code(status);
+    for header in headers {
+        response.add_header(header);
+    }
+    if let Err(e) = request.respond(response) {
+        output::warn(format!("Failed to send response: {}", e));
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// A saved command or code block, copied back out by name.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Snippet {
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub saved: DateTime<Utc>,
+}
+
+impl Snippet {
+    pub fn summary(&self) -> String {
+        let first = self.content.trim().lines().next().unwrap_or_default();
+        let mut line: String = first.chars().take(60).collect();
+        if first.chars().count() > 60 || self.content.trim().lines().count() > 1 {
+            line.push('…');
+        }
+        line
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("snippets.json"))
+}
+
+/// Saved snippets by name.
+pub fn load() -> Result<BTreeMap<String, Snippet>> {
+    let Some(path) = path() else {
+        return Ok(BTreeMap::new());
+    };
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read snippets {}", path.display()))?;
+    serde_json::from_str(&text)
+        .with_context(|| format!("Failed to parse snippets {}", path.display()))
+}
+
+/// Saves a snippet, returning whether it replaced one with the same name.
+pub fn save(name: &str, content: &str, tags: Vec<String>) -> Result<bool> {
+    let path = path().context("Could not determine a data directory for snippets")?;
+    let mut snippets = load()?;
+    let snippet = Snippet {
+        content: content.to_string(),
+        tags,
+        saved: Utc::now(),
+    };
+    let replaced = snippets.insert(name.to_string(), snippet).is_some();
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(serde_json::to_string_pretty(&snippets)?.as_bytes()))
+        .with_context(|| format!("Failed to write snippets {}", path.display()))?;
+    Ok(replaced)
+}
+
+pub fn get(name: &str) -> Result<Snippet> {
+    match load()?.remove(name) {
+        Some(snippet) => Ok(snippet),
+        None => bail!("No snippet named {:?}; run `search snip list`", name),
+    }
+}
+
+/// The contents of the fenced code blocks in a Markdown reply.
+pub fn code_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        match &mut current {
+            Some((fence, lines)) => {
+                if trimmed.starts_with(fence.as_str())
+                    && trimmed.trim_matches('`').trim().is_empty()
+                {
+                    blocks.push(lines.join("\n"));
+                    current = None;
+                } else {
+                    lines.push(line);
+                }
+            }
+            None if trimmed.starts_with("```") => {
+                let fence: String = trimmed.chars().take_while(|c| *c == '`').collect();
+                current = Some((fence, Vec::new()));
+            }
+            None => {}
+        }
+    }
+    blocks
+}
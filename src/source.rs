@@ -0,0 +1,372 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+
+use crate::config::Config;
+use crate::context::{self, ContextFile};
+use crate::error::SearchError;
+use crate::format::Paste;
+use crate::{
+    clipboard, deps, env, ground, lsp, manifest, ops, output, redact, ring, sysinfo, tail,
+};
+
+/// Context gathered so far, in the order it will appear in the prompt.
+#[derive(Default)]
+pub struct Collected {
+    pub pastes: Vec<Paste>,
+    pub files: Vec<ContextFile>,
+}
+
+impl Collected {
+    pub fn push_file(&mut self, file: ContextFile) {
+        if !self.files.iter().any(|f| f.path == file.path) {
+            self.files.push(file);
+        }
+    }
+}
+
+/// Adds pasted text or files to the context of a query.
+pub trait ContextSource {
+    fn collect(&self, collected: &mut Collected, config: &Config) -> Result<(), SearchError>;
+}
+
+impl ContextSource for Paste {
+    fn collect(&self, collected: &mut Collected, _config: &Config) -> Result<(), SearchError> {
+        collected.pastes.push(self.clone());
+        Ok(())
+    }
+}
+
+/// Top web search results for a query, fetched from the configured search API.
+pub struct Ground {
+    pub query: String,
+}
+
+impl ContextSource for Ground {
+    fn collect(&self, collected: &mut Collected, config: &Config) -> Result<(), SearchError> {
+        let content = ground::context(&config.ground, &self.query)?;
+        collected.pastes.push(Paste {
+            source: "web",
+            content,
+        });
+        Ok(())
+    }
+}
+
+/// The end of a log file.
+pub struct Tail {
+    pub path: PathBuf,
+    pub lines: usize,
+    pub since: Option<Duration>,
+    pub max_file_bytes: usize,
+}
+
+impl ContextSource for Tail {
+    fn collect(&self, collected: &mut Collected, _config: &Config) -> Result<(), SearchError> {
+        let file = tail::read(&self.path, self.lines, self.since, self.max_file_bytes)?;
+        collected.push_file(file);
+        Ok(())
+    }
+}
+
+/// Recent logs of a Docker container.
+pub struct DockerLogs {
+    pub container: String,
+    pub lines: usize,
+    pub since: Option<Duration>,
+    pub max_file_bytes: usize,
+}
+
+impl ContextSource for DockerLogs {
+    fn collect(&self, collected: &mut Collected, _config: &Config) -> Result<(), SearchError> {
+        let _progress = output::progress(format!("Reading logs of {}", self.container));
+        collected.pastes.push(ops::docker_logs(
+            &self.container,
+            self.lines,
+            self.since,
+            self.max_file_bytes,
+        )?);
+        Ok(())
+    }
+}
+
+/// Output of a kubectl command.
+pub struct Kubectl {
+    pub command: String,
+    pub max_file_bytes: usize,
+}
+
+impl ContextSource for Kubectl {
+    fn collect(&self, collected: &mut Collected, _config: &Config) -> Result<(), SearchError> {
+        let _progress = output::progress(format!("Running kubectl {}", self.command));
+        collected
+            .pastes
+            .push(ops::kubectl(&self.command, self.max_file_bytes)?);
+        Ok(())
+    }
+}
+
+/// OS, shell, tool versions, and selected environment variables.
+pub struct Env {
+    pub pattern: String,
+    pub values: bool,
+}
+
+impl ContextSource for Env {
+    fn collect(&self, collected: &mut Collected, _config: &Config) -> Result<(), SearchError> {
+        collected.pastes.push(Paste {
+            source: "env",
+            content: env::snapshot(&self.pattern, self.values)?,
+        });
+        Ok(())
+    }
+}
+
+/// OS, kernel, CPU, memory, and toolchain versions.
+pub struct SysInfo;
+
+impl ContextSource for SysInfo {
+    fn collect(&self, collected: &mut Collected, _config: &Config) -> Result<(), SearchError> {
+        collected.pastes.push(Paste {
+            source: "sysinfo",
+            content: sysinfo::summary(),
+        });
+        Ok(())
+    }
+}
+
+/// Text files under a list of files and directories.
+pub struct Paths {
+    pub paths: Vec<String>,
+    pub max_file_bytes: usize,
+}
+
+impl ContextSource for Paths {
+    fn collect(&self, collected: &mut Collected, _config: &Config) -> Result<(), SearchError> {
+        let files = context::collect(&self.paths, self.max_file_bytes)?;
+        if files.is_empty() {
+            return Err(SearchError::NoContext);
+        }
+        collected.files.extend(files);
+        Ok(())
+    }
+}
+
+/// Paths loaded by conscious-data/contextualize, read back from the clipboard.
+pub struct Contextualize {
+    pub paths: Vec<String>,
+}
+
+const CONTEXTUALIZE_TIMEOUT_SECS: u64 = 120;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static CHILD_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Stops a running contextualize, which then reports its stderr, returning false if none runs.
+///
+/// Signal handling belongs to the application, so call this from its Ctrl-C handler.
+pub fn interrupt() -> bool {
+    let running = CHILD_RUNNING.load(Ordering::SeqCst);
+    if running {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+    running
+}
+
+impl ContextSource for Contextualize {
+    fn collect(&self, collected: &mut Collected, config: &Config) -> Result<(), SearchError> {
+        let secs = config
+            .contextualize_timeout_secs
+            .unwrap_or(CONTEXTUALIZE_TIMEOUT_SECS);
+        let progress = output::progress("Running contextualize");
+        let mut command = Command::new("contextualize");
+        command
+            .arg("cat")
+            .arg("--output")
+            .arg("clipboard")
+            .args(&self.paths)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        // Its own process group, so killing it also stops anything it started.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let mut child = command
+            .spawn()
+            .context("Failed to run contextualize command")?;
+        let mut pipe = child.stderr.take().expect("stderr is piped");
+        let stderr = std::thread::spawn(move || {
+            let mut stderr = String::new();
+            let _ = pipe.read_to_string(&mut stderr);
+            stderr
+        });
+
+        CHILD_RUNNING.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + Duration::from_secs(secs);
+        let status = loop {
+            if let Some(status) = child
+                .try_wait()
+                .context("Failed to wait for contextualize")?
+            {
+                break Some(status);
+            }
+            if INTERRUPTED.load(Ordering::SeqCst) || Instant::now() >= deadline {
+                kill(&mut child);
+                break None;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        CHILD_RUNNING.store(false, Ordering::SeqCst);
+        drop(progress);
+        let stderr = stderr.join().unwrap_or_default().trim().to_string();
+
+        match status {
+            Some(status) if status.success() => {}
+            Some(_) => return Err(SearchError::ContextualizeFailed { stderr }),
+            None if INTERRUPTED.swap(false, Ordering::SeqCst) => {
+                if !stderr.is_empty() {
+                    output::warn(format!("contextualize stderr: {}", stderr));
+                }
+                return Err(SearchError::Interrupted);
+            }
+            None => return Err(SearchError::ContextualizeTimedOut { secs, stderr }),
+        }
+
+        collected.pastes.push(Paste {
+            source: "contextualize",
+            content: clipboard::get_content()?,
+        });
+        Ok(())
+    }
+}
+
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    // SAFETY: kill(2) only sends a signal; the group id is the child we spawned.
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+pub struct Clipboard(pub Box<dyn clipboard::Clipboard>);
+
+impl Clipboard {
+    fn read(&self) -> Result<String, SearchError> {
+        let html = self.0.get_html()?;
+        match html.as_deref().and_then(clipboard::to_markdown) {
+            Some(markdown) => Ok(markdown),
+            None => self.0.get_text(),
+        }
+    }
+}
+
+impl ContextSource for Clipboard {
+    fn collect(&self, collected: &mut Collected, _config: &Config) -> Result<(), SearchError> {
+        let content = self.read()?;
+        if content.trim().is_empty() {
+            output::warn(if self.0.has_image() {
+                "The clipboard holds an image, which can't be sent in a URL; continuing with just the prompt"
+            } else {
+                "The clipboard is empty; continuing with just the prompt"
+            });
+            return Ok(());
+        }
+        collected.pastes.push(Paste {
+            source: "clipboard",
+            content,
+        });
+        Ok(())
+    }
+}
+
+/// A capture from the clipboard history, where 1 is the clipboard as it is now.
+pub struct ClipboardRing {
+    pub clipboard: Clipboard,
+    pub index: usize,
+    /// Whether to add the current clipboard to the history
+    pub record: bool,
+}
+
+impl ContextSource for ClipboardRing {
+    fn collect(&self, collected: &mut Collected, config: &Config) -> Result<(), SearchError> {
+        if self.record {
+            // An older capture is still usable when the clipboard isn't, e.g. over SSH.
+            let current = match self.clipboard.read() {
+                Ok(content) => content,
+                Err(_) if self.index > 1 => String::new(),
+                Err(e) => return Err(e),
+            };
+            if !current.trim().is_empty() && !has_secrets(&current, config)? {
+                if let Err(e) = ring::push(&config.history, &current) {
+                    output::warn(format!("{:#}", e));
+                }
+            }
+        }
+        if self.index == 1 {
+            return self.clipboard.collect(collected, config);
+        }
+        collected.pastes.push(Paste {
+            source: "clipboard",
+            content: ring::get(&config.history, self.index)?.content,
+        });
+        Ok(())
+    }
+}
+
+/// Whether the redaction rules would replace anything, so the text shouldn't be kept on disk.
+fn has_secrets(text: &str, config: &Config) -> Result<bool, SearchError> {
+    let dir = std::env::current_dir().unwrap_or_default();
+    Ok(redact::Redactor::new(&config.redact, &dir)?
+        .find(text)
+        .iter()
+        .any(|finding| finding.allowed_by.is_none()))
+}
+
+/// A file along with the local modules it imports.
+pub struct Deps {
+    pub root: PathBuf,
+    pub max_file_bytes: usize,
+}
+
+impl ContextSource for Deps {
+    fn collect(&self, collected: &mut Collected, _config: &Config) -> Result<(), SearchError> {
+        let file = context::read_path(&self.root, self.max_file_bytes)?;
+        let imports = deps::local_dependencies(&self.root, &file.content);
+        collected.push_file(file);
+        for import in imports {
+            collected.push_file(context::read_path(&import, self.max_file_bytes)?);
+        }
+        Ok(())
+    }
+}
+
+/// Definitions of symbols used in the files collected so far, resolved via a language server.
+pub struct Definitions;
+
+impl ContextSource for Definitions {
+    fn collect(&self, collected: &mut Collected, config: &Config) -> Result<(), SearchError> {
+        let _progress = output::progress("Resolving definitions");
+        let definitions = lsp::definitions(&collected.files, &config.lsp)?;
+        collected.files.extend(definitions);
+        Ok(())
+    }
+}
+
+/// The project manifest and a lockfile summary, placed before the other files.
+pub struct Manifest {
+    pub max_file_bytes: usize,
+}
+
+impl ContextSource for Manifest {
+    fn collect(&self, collected: &mut Collected, _config: &Config) -> Result<(), SearchError> {
+        let mut manifests = manifest::collect(self.max_file_bytes)?;
+        manifests.retain(|m| !collected.files.iter().any(|f| f.path == m.path));
+        collected.files.splice(0..0, manifests);
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use chrono::{Duration, Local, NaiveDate};
+
+use crate::context::format_size;
+use crate::history::Entry;
+use crate::output;
+
+#[derive(Default)]
+struct Tally {
+    queries: usize,
+    bytes: usize,
+}
+
+impl Tally {
+    fn add(&mut self, entry: &Entry) {
+        self.queries += 1;
+        self.bytes += entry.composed.len();
+    }
+}
+
+pub fn print(entries: &[Entry], days: u32) {
+    if entries.is_empty() {
+        println!("No queries recorded yet.");
+        return;
+    }
+
+    let since = Local::now().date_naive() - Duration::days(i64::from(days.max(1)) - 1);
+    let mut per_day: BTreeMap<NaiveDate, Tally> = BTreeMap::new();
+    let mut per_provider: BTreeMap<&str, Tally> = BTreeMap::new();
+    let mut per_template: BTreeMap<&str, Tally> = BTreeMap::new();
+    let mut total = Tally::default();
+
+    for entry in entries {
+        let date = entry.timestamp.with_timezone(&Local).date_naive();
+        if date < since || entry.imported {
+            continue;
+        }
+        per_day.entry(date).or_default().add(entry);
+        per_provider.entry(&entry.provider).or_default().add(entry);
+        if let Some(template) = &entry.template {
+            per_template.entry(template).or_default().add(entry);
+        }
+        total.add(entry);
+    }
+
+    println!("{}", output::bold(&format!("Last {} days", days.max(1))));
+    println!();
+    print_table(
+        "date",
+        per_day.iter().map(|(date, t)| (date.to_string(), t)),
+        &total,
+    );
+    println!();
+
+    print_table(
+        "provider",
+        most_used(per_provider)
+            .iter()
+            .map(|(name, t)| (name.to_string(), t)),
+        &total,
+    );
+
+    if !per_template.is_empty() {
+        println!();
+        let templated = Tally {
+            queries: per_template.values().map(|t| t.queries).sum(),
+            bytes: per_template.values().map(|t| t.bytes).sum(),
+        };
+        print_table(
+            "template",
+            most_used(per_template)
+                .iter()
+                .map(|(name, t)| (name.to_string(), t)),
+            &templated,
+        );
+    }
+}
+
+fn most_used(tallies: BTreeMap<&str, Tally>) -> Vec<(&str, Tally)> {
+    let mut rows: Vec<(&str, Tally)> = tallies.into_iter().collect();
+    rows.sort_by(|a, b| b.1.queries.cmp(&a.1.queries).then(a.0.cmp(b.0)));
+    rows
+}
+
+fn print_table<'a>(heading: &str, rows: impl Iterator<Item = (String, &'a Tally)>, total: &Tally) {
+    let header = format!("{:<12} {:>8} {:>10}", heading, "queries", "sent");
+    println!("{}", output::bold(&header));
+    for (label, tally) in rows {
+        println!(
+            "{:<12} {:>8} {:>10}",
+            label,
+            tally.queries,
+            format_size(tally.bytes)
+        );
+    }
+    let footer = format!(
+        "{:<12} {:>8} {:>10}",
+        "total",
+        total.queries,
+        format_size(total.bytes)
+    );
+    println!("{}", output::bold(&footer));
+}
@@ -0,0 +1,75 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config::SummarizeConfig;
+use crate::context::ContextFile;
+use crate::output;
+
+const PROMPT: &str = "Summarize the following file for another model that will answer questions \
+about this codebase. Cover its purpose and list the key types, functions, and their signatures. \
+Be brief and reply with the summary only.";
+
+#[derive(Deserialize)]
+struct GenerateResponse {
+    response: String,
+}
+
+pub fn fit_to_budget(
+    files: &mut [ContextFile],
+    budget: usize,
+    config: &SummarizeConfig,
+) -> Result<()> {
+    let mut total: usize = files.iter().map(|f| f.content.len()).sum();
+
+    for file in files.iter_mut().rev() {
+        if total <= budget {
+            break;
+        }
+        if file.note.is_some() {
+            continue;
+        }
+
+        let progress = output::progress(format!(
+            "Summarizing {} with {}",
+            file.path.display(),
+            config.model()
+        ));
+        let digest = summarize(file, config)?;
+        drop(progress);
+        if digest.len() >= file.content.len() {
+            continue;
+        }
+
+        total -= file.content.len() - digest.len();
+        file.content = digest;
+        file.lang = None;
+        file.truncated = false;
+        file.note = Some(format!("summarized by {}", config.model()));
+    }
+
+    Ok(())
+}
+
+fn summarize(file: &ContextFile, config: &SummarizeConfig) -> Result<String> {
+    let prompt = format!(
+        "{}\n\nFile: {}\n\n{}",
+        PROMPT,
+        file.path.display(),
+        file.content
+    );
+    let url = format!("{}/api/generate", config.url().trim_end_matches('/'));
+
+    let response: GenerateResponse = ureq::post(&url)
+        .send_json(json!({
+            "model": config.model(),
+            "prompt": prompt,
+            "stream": false,
+        }))
+        .with_context(|| format!("Failed to reach local model at {}", url))?
+        .body_mut()
+        .read_json()
+        .context("Failed to parse local model response")?;
+
+    Ok(response.response.trim().to_string())
+}
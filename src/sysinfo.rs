@@ -0,0 +1,79 @@
+use std::process::{Command, Stdio};
+
+use crate::env;
+
+const GIB: f64 = (1u64 << 30) as f64;
+
+/// A compact description of the machine: OS and distro, kernel, CPU, memory, and toolchains.
+pub fn summary() -> String {
+    let mut lines = vec![format!(
+        "OS: {} ({})",
+        distro().unwrap_or_else(|| std::env::consts::OS.to_string()),
+        std::env::consts::ARCH
+    )];
+    if let Some(kernel) = run("uname", &["-sr"]) {
+        lines.push(format!("Kernel: {}", kernel));
+    }
+
+    let cores = std::thread::available_parallelism().map_or(0, |n| n.get());
+    lines.push(match cpu() {
+        Some(model) => format!("CPU: {}, {} threads", model, cores),
+        None => format!("CPU: {} threads", cores),
+    });
+    if let Some(bytes) = memory() {
+        lines.push(format!("RAM: {:.1} GB", bytes as f64 / GIB));
+    }
+
+    for (tool, version) in env::tool_versions(env::TOOLS) {
+        lines.push(format!("{}: {}", tool, version));
+    }
+    lines.join("\n")
+}
+
+fn distro() -> Option<String> {
+    if cfg!(target_os = "macos") {
+        let name = run("sw_vers", &["-productName"])?;
+        let version = run("sw_vers", &["-productVersion"]).unwrap_or_default();
+        return Some(format!("{} {}", name, version).trim().to_string());
+    }
+    if cfg!(windows) {
+        return run("cmd", &["/c", "ver"]);
+    }
+    let release = std::fs::read_to_string("/etc/os-release").ok()?;
+    release.lines().find_map(|line| {
+        let value = line.strip_prefix("PRETTY_NAME=")?;
+        Some(value.trim_matches('"').to_string())
+    })
+}
+
+fn cpu() -> Option<String> {
+    if cfg!(target_os = "macos") {
+        return run("sysctl", &["-n", "machdep.cpu.brand_string"]);
+    }
+    let info = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+    info.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        matches!(key.trim(), "model name" | "Model" | "Hardware").then(|| value.trim().to_string())
+    })
+}
+
+fn memory() -> Option<usize> {
+    if cfg!(target_os = "macos") {
+        return run("sysctl", &["-n", "hw.memsize"])?.parse().ok();
+    }
+    let info = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = info.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kib: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !text.is_empty()).then_some(text)
+}
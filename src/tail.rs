@@ -0,0 +1,175 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+
+use crate::context::{format_size, ContextFile};
+use crate::output;
+
+/// Parses durations like `90s`, `10m`, `2h`, or `1d`.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration {:?}, expected e.g. 10m or 2h", text))?;
+    let seconds = match unit {
+        "s" | "" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "unknown duration unit {:?}, use s, m, h, or d",
+                unit
+            ))
+        }
+    };
+    let seconds = number
+        .checked_mul(seconds)
+        .ok_or_else(|| format!("duration {:?} is too large", text))?;
+    Ok(Duration::from_secs(seconds))
+}
+
+/// The end of a log file: its last `lines` lines, or with `since`, everything from the first line
+/// stamped within that window.
+pub fn read(
+    path: &Path,
+    lines: usize,
+    since: Option<Duration>,
+    max_bytes: usize,
+) -> Result<ContextFile> {
+    let (text, size, cut) =
+        read_end(path, max_bytes).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let window = since.and_then(|since| Some((since, since_window(&text, since)?)));
+    let (content, mut note) = match window {
+        Some((since, window)) => (window, format!("entries from the last {}", humanize(since))),
+        None => {
+            if since.is_some() {
+                output::warn(format!(
+                    "No timestamps within the window found in {}; using its last {} lines",
+                    path.display(),
+                    lines
+                ));
+            }
+            (last_lines(&text, lines), format!("last {} lines", lines))
+        }
+    };
+    if cut && content.len() == text.len() {
+        note.push_str(&format!(", capped at {}", format_size(max_bytes)));
+    }
+
+    Ok(ContextFile {
+        path: path.to_path_buf(),
+        lang: None,
+        size,
+        truncated: false,
+        note: Some(note),
+        content,
+    })
+}
+
+/// Reads at most `max_bytes` from the end of the file, starting at a line boundary.
+fn read_end(path: &Path, max_bytes: usize) -> std::io::Result<(String, usize, bool)> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len() as usize;
+    let start = size.saturating_sub(max_bytes);
+    file.seek(SeekFrom::Start(start as u64))?;
+    let mut bytes = Vec::with_capacity(size - start);
+    file.read_to_end(&mut bytes)?;
+
+    let mut text = String::from_utf8_lossy(&bytes).into_owned();
+    if start > 0 {
+        // The first line is partial, so drop it.
+        let first = text.find('\n').map_or(text.len(), |i| i + 1);
+        text.drain(..first);
+    }
+    Ok((text, size, start > 0))
+}
+
+/// The last `lines` lines of the text, without the trailing newline.
+pub fn last_lines(text: &str, lines: usize) -> String {
+    if lines == 0 {
+        return String::new();
+    }
+    let text = text.trim_end_matches('\n');
+    let start = text
+        .rmatch_indices('\n')
+        .nth(lines.saturating_sub(1))
+        .map_or(0, |(i, _)| i + 1);
+    text[start..].to_string()
+}
+
+fn since_window(text: &str, since: Duration) -> Option<String> {
+    let cutoff = chrono::Duration::from_std(since)
+        .ok()
+        .and_then(|since| Utc::now().checked_sub_signed(since))
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if timestamp(line).is_some_and(|time| time >= cutoff) {
+            return Some(text[offset..].trim_end_matches('\n').to_string());
+        }
+        offset += line.len();
+    }
+    None
+}
+
+static ISO: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\[?(\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2})(?:[.,]\d+)?(Z|[+-]\d{2}:?\d{2})?")
+        .expect("valid regex")
+});
+static SYSLOG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([A-Z][a-z]{2} +\d{1,2} \d{2}:\d{2}:\d{2})").expect("valid regex")
+});
+
+/// The timestamp at the start of an ISO-8601 or syslog-style log line.
+fn timestamp(line: &str) -> Option<DateTime<Utc>> {
+    if let Some(caps) = ISO.captures(line) {
+        let time =
+            NaiveDateTime::parse_from_str(&caps[1].replace('T', " "), "%Y-%m-%d %H:%M:%S").ok()?;
+        return match caps.get(2).map(|m| m.as_str()) {
+            Some("Z") => Some(time.and_utc()),
+            Some(offset) => {
+                let offset = offset.replace(':', "");
+                DateTime::parse_from_str(
+                    &format!("{} {}", time.format("%Y-%m-%d %H:%M:%S"), offset),
+                    "%Y-%m-%d %H:%M:%S %z",
+                )
+                .ok()
+                .map(|time| time.with_timezone(&Utc))
+            }
+            None => local(time),
+        };
+    }
+
+    let caps = SYSLOG.captures(line)?;
+    let stamped = format!("{} {}", Local::now().year(), &caps[1]);
+    let time = NaiveDateTime::parse_from_str(&stamped, "%Y %b %e %H:%M:%S").ok()?;
+    local(time)
+}
+
+fn local(time: NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn humanize(duration: Duration) -> String {
+    match duration.as_secs() {
+        s if s % 86_400 == 0 && s > 0 => format!("{}d", s / 86_400),
+        s if s % 3_600 == 0 && s > 0 => format!("{}h", s / 3_600),
+        s if s % 60 == 0 && s > 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
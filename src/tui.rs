@@ -0,0 +1,386 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Position, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::config::Config;
+use crate::context::{self, estimate_tokens, format_size};
+use crate::output;
+use crate::pipeline::{self, ComposeOptions};
+use crate::provider::{self, Provider};
+use crate::redact::Redactor;
+use crate::source::{self, ContextSource};
+
+/// Idle time before the preview is recomposed, so typing doesn't re-read files on every key.
+const REFRESH_DELAY: Duration = Duration::from_millis(250);
+
+const HELP: &str = "Tab next pane · Space toggle file · a toggle all · Ctrl-S send · Esc cancel";
+
+/// What the composer settled on, sent through the normal query pipeline.
+pub struct Selection {
+    pub provider: String,
+    pub prompt: String,
+    pub files: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Prompt,
+    Files,
+    Provider,
+    Preview,
+}
+
+const PANES: [Pane; 4] = [Pane::Prompt, Pane::Files, Pane::Provider, Pane::Preview];
+
+struct Candidate {
+    path: PathBuf,
+    size: usize,
+    selected: bool,
+}
+
+struct App<'a> {
+    config: &'a Config,
+    options: ComposeOptions,
+    max_file_bytes: usize,
+    providers: Vec<String>,
+    provider: usize,
+    prompt: String,
+    files: Vec<Candidate>,
+    file_state: ListState,
+    focus: Pane,
+    preview: String,
+    preview_scroll: u16,
+    status: String,
+    dirty: bool,
+}
+
+/// Runs the composer over the files under `paths`, returning None when cancelled.
+pub fn run(
+    config: &Config,
+    options: ComposeOptions,
+    paths: &[String],
+    preselect: bool,
+    max_file_bytes: usize,
+) -> Result<Option<Selection>> {
+    let files = context::walk(paths)?
+        .into_iter()
+        .map(|path| {
+            let size = std::fs::metadata(&path).map_or(0, |m| m.len() as usize);
+            let path = path
+                .strip_prefix(".")
+                .map(Path::to_path_buf)
+                .unwrap_or(path);
+            Candidate {
+                path,
+                size,
+                selected: preselect,
+            }
+        })
+        .collect();
+
+    let providers = provider::names(config);
+    let provider = providers
+        .iter()
+        .position(|name| *name == options.provider)
+        .unwrap_or(0);
+    let mut app = App {
+        config,
+        prompt: options.prompt.clone(),
+        options,
+        max_file_bytes,
+        providers,
+        provider,
+        files,
+        file_state: ListState::default().with_selected(Some(0)),
+        focus: Pane::Prompt,
+        preview: String::new(),
+        preview_scroll: 0,
+        status: String::new(),
+        dirty: true,
+    };
+
+    output::set_quiet(true);
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    output::set_quiet(false);
+    result
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<Selection>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(REFRESH_DELAY)? {
+                if self.dirty {
+                    self.refresh();
+                    self.dirty = false;
+                }
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if ctrl => return Ok(None),
+                KeyCode::Char('s') if ctrl => {
+                    if self.prompt.trim().is_empty() {
+                        self.status = "Write a prompt before sending".to_string();
+                        continue;
+                    }
+                    return Ok(Some(self.selection()));
+                }
+                KeyCode::Tab => self.cycle_focus(1),
+                KeyCode::BackTab => self.cycle_focus(PANES.len() - 1),
+                _ => self.handle(key),
+            }
+        }
+    }
+
+    fn cycle_focus(&mut self, step: usize) {
+        let current = PANES.iter().position(|p| *p == self.focus).unwrap_or(0);
+        self.focus = PANES[(current + step) % PANES.len()];
+    }
+
+    fn handle(&mut self, key: KeyEvent) {
+        match self.focus {
+            Pane::Prompt => match key.code {
+                KeyCode::Char(c) => self.prompt.push(c),
+                KeyCode::Enter => self.prompt.push('\n'),
+                KeyCode::Backspace => {
+                    self.prompt.pop();
+                }
+                _ => return,
+            },
+            Pane::Files => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.file_state.select_previous();
+                    return;
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.file_state.select_next();
+                    return;
+                }
+                KeyCode::Char(' ') | KeyCode::Enter => {
+                    let selected = self.file_state.selected().unwrap_or(0);
+                    if let Some(file) = self.files.get_mut(selected) {
+                        file.selected = !file.selected;
+                    }
+                }
+                KeyCode::Char('a') => {
+                    let select = !self.files.iter().all(|f| f.selected);
+                    for file in &mut self.files {
+                        file.selected = select;
+                    }
+                }
+                _ => return,
+            },
+            Pane::Provider => match key.code {
+                KeyCode::Left | KeyCode::Up | KeyCode::Char('h') => {
+                    self.provider = self
+                        .provider
+                        .checked_sub(1)
+                        .unwrap_or(self.providers.len() - 1);
+                }
+                KeyCode::Right | KeyCode::Down | KeyCode::Char('l') => {
+                    self.provider = (self.provider + 1) % self.providers.len();
+                }
+                _ => return,
+            },
+            Pane::Preview => {
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.preview_scroll = self.preview_scroll.saturating_sub(1)
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        self.preview_scroll = self.preview_scroll.saturating_add(1)
+                    }
+                    KeyCode::PageUp => self.preview_scroll = self.preview_scroll.saturating_sub(20),
+                    KeyCode::PageDown => {
+                        self.preview_scroll = self.preview_scroll.saturating_add(20)
+                    }
+                    _ => {}
+                }
+                return;
+            }
+        }
+        self.dirty = true;
+    }
+
+    fn selected_files(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|f| f.selected)
+            .map(|f| f.path.display().to_string())
+            .collect()
+    }
+
+    fn selection(&self) -> Selection {
+        Selection {
+            provider: self.providers[self.provider].clone(),
+            prompt: self.prompt.trim().to_string(),
+            files: self.selected_files(),
+        }
+    }
+
+    /// Composes the query as it would be sent, without summarizing or warning on the terminal.
+    fn refresh(&mut self) {
+        let mut options = self.options.clone();
+        options.provider = self.providers[self.provider].clone();
+        options.prompt = self.prompt.trim().to_string();
+        options.summarize = false;
+        options.no_redact = true;
+
+        let files = self.selected_files();
+        let mut sources: Vec<Box<dyn ContextSource>> = Vec::new();
+        if !files.is_empty() {
+            sources.push(Box::new(source::Paths {
+                paths: files,
+                max_file_bytes: self.max_file_bytes,
+            }));
+        }
+
+        let composed = pipeline::compose(&options, &sources, self.config);
+        let composed = match composed {
+            Ok(composed) => composed,
+            Err(e) => {
+                self.preview.clear();
+                self.status = e.to_string();
+                return;
+            }
+        };
+        let mut query = composed.query;
+        if !self.options.no_redact {
+            let dir = std::env::current_dir().unwrap_or_default();
+            match Redactor::new(&self.config.redact, &dir) {
+                Ok(redactor) => query = redactor.redact(&query).text,
+                Err(e) => self.status = format!("{:#}", e),
+            }
+        }
+
+        let url_length = composed.provider.query_url(&query).len();
+        let delivery = if url_length <= composed.provider.max_url_length() {
+            "fits in the URL"
+        } else {
+            "too long for the URL, sent through the clipboard"
+        };
+        self.status = format!(
+            "{} chars, ~{} tokens, {}",
+            query.chars().count(),
+            estimate_tokens(query.len()),
+            delivery
+        );
+        self.preview = query;
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(main);
+        let [prompt_area, provider_area, preview_area] = Layout::vertical([
+            Constraint::Length(7),
+            Constraint::Length(3),
+            Constraint::Min(0),
+        ])
+        .areas(right);
+
+        self.draw_files(frame, left);
+        self.draw_prompt(frame, prompt_area);
+        self.draw_providers(frame, provider_area);
+
+        let preview = Paragraph::new(self.preview.as_str())
+            .wrap(Wrap { trim: false })
+            .scroll((self.preview_scroll, 0))
+            .block(self.block(Pane::Preview, format!("Preview ({})", self.status)));
+        frame.render_widget(preview, preview_area);
+        frame.render_widget(Line::from(HELP).dim(), help);
+    }
+
+    fn draw_files(&mut self, frame: &mut Frame, area: Rect) {
+        let selected: Vec<&Candidate> = self.files.iter().filter(|f| f.selected).collect();
+        let bytes: usize = selected
+            .iter()
+            .map(|f| f.size.min(self.max_file_bytes))
+            .sum();
+        let title = format!(
+            "Files ({} selected, {}, ~{} tokens)",
+            selected.len(),
+            format_size(bytes),
+            estimate_tokens(bytes)
+        );
+
+        let items: Vec<ListItem> = self
+            .files
+            .iter()
+            .map(|file| {
+                let mark = if file.selected { "[x]" } else { "[ ]" };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{} {} ", mark, file.path.display())),
+                    Span::raw(format_size(file.size)).dim(),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::new().reversed())
+            .block(self.block(Pane::Files, title));
+        frame.render_stateful_widget(list, area, &mut self.file_state);
+    }
+
+    fn draw_prompt(&self, frame: &mut Frame, area: Rect) {
+        let lines: Vec<&str> = self.prompt.split('\n').collect();
+        let height = area.height.saturating_sub(2) as usize;
+        let scroll = lines.len().saturating_sub(height);
+        let prompt = Paragraph::new(self.prompt.as_str())
+            .scroll((scroll as u16, 0))
+            .block(self.block(Pane::Prompt, "Prompt".to_string()));
+        frame.render_widget(prompt, area);
+
+        if self.focus == Pane::Prompt {
+            let last = lines.last().map_or(0, |line| line.chars().count());
+            let row = (lines.len() - 1 - scroll) as u16;
+            frame.set_cursor_position(Position::new(
+                (area.x + 1 + last as u16).min(area.right().saturating_sub(2)),
+                area.y + 1 + row,
+            ));
+        }
+    }
+
+    fn draw_providers(&self, frame: &mut Frame, area: Rect) {
+        let mut spans = Vec::new();
+        for (i, name) in self.providers.iter().enumerate() {
+            let span = Span::raw(format!(" {} ", name));
+            spans.push(if i == self.provider {
+                span.reversed()
+            } else {
+                span
+            });
+        }
+        let providers =
+            Paragraph::new(Line::from(spans)).block(self.block(Pane::Provider, "Provider".into()));
+        frame.render_widget(providers, area);
+    }
+
+    fn block(&self, pane: Pane, title: String) -> Block<'static> {
+        let block = Block::bordered().title(title);
+        if self.focus == pane {
+            block.border_style(Style::new().yellow())
+        } else {
+            block
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use ureq::Agent;
+
+use crate::config::WebDriverConfig;
+use crate::output;
+use crate::provider::Provider;
+
+const ELEMENT_KEY: &str = "element-6066-11e4-a52e-4f735466cecf";
+const ENTER: &str = "\u{e007}";
+// Shift+Enter then release the modifier, so multi-line prompts aren't sent line by line.
+const NEWLINE: &str = "\u{e008}\u{e007}\u{e000}";
+
+/// Opens the provider in a WebDriver-controlled browser, types the prompt, and submits it.
+pub fn submit(config: &WebDriverConfig, provider: &dyn Provider, prompt: &str) -> Result<()> {
+    let selector = provider.input_selector().with_context(|| {
+        format!(
+            "Provider {} has no input_selector configured for --webdriver",
+            provider.name()
+        )
+    })?;
+
+    let agent: Agent = Agent::config_builder()
+        .http_status_as_error(false)
+        .build()
+        .into();
+    let base = config.url().trim_end_matches('/');
+
+    let session = command(
+        &agent,
+        &format!("{}/session", base),
+        json!({ "capabilities": { "alwaysMatch": { "browserName": config.browser() } } }),
+    )
+    .with_context(|| format!("Failed to start a browser session via {}", base))?;
+    let session = session["sessionId"]
+        .as_str()
+        .context("WebDriver did not return a session id")?;
+    let session = format!("{}/session/{}", base, session);
+
+    command(
+        &agent,
+        &format!("{}/url", session),
+        json!({ "url": provider.home() }),
+    )?;
+    let progress = output::progress(format!("Waiting for {} to load", provider.name()));
+    let element = wait_for_element(&agent, &session, selector, Duration::from_secs(30))?;
+    drop(progress);
+
+    let text = prompt.replace('\n', NEWLINE) + ENTER;
+    command(
+        &agent,
+        &format!("{}/element/{}/value", session, element),
+        json!({ "text": text }),
+    )
+    .context("Failed to type the prompt")?;
+    Ok(())
+}
+
+fn wait_for_element(
+    agent: &Agent,
+    session: &str,
+    selector: &str,
+    timeout: Duration,
+) -> Result<String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let found = command(
+            agent,
+            &format!("{}/element", session),
+            json!({ "using": "css selector", "value": selector }),
+        );
+        match found {
+            Ok(value) => {
+                return value[ELEMENT_KEY]
+                    .as_str()
+                    .map(str::to_string)
+                    .context("WebDriver returned an element without a reference");
+            }
+            Err(_) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(500)),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Chat input {:?} did not appear", selector))
+            }
+        }
+    }
+}
+
+fn command(agent: &Agent, url: &str, body: Value) -> Result<Value> {
+    let mut response = agent
+        .post(url)
+        .send_json(body)
+        .with_context(|| format!("Failed to reach WebDriver at {}", url))?;
+    let body: Value = response
+        .body_mut()
+        .read_json()
+        .context("Failed to parse WebDriver response")?;
+
+    let value = body["value"].clone();
+    if let Some(error) = value["error"].as_str() {
+        anyhow::bail!(
+            "WebDriver error {}: {}",
+            error,
+            value["message"].as_str().unwrap_or("")
+        );
+    }
+    Ok(value)
+}
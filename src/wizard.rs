@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+
+use crate::browser::Browser;
+use crate::config::{self, Config, ProviderConfig};
+use crate::provider::{self, Encoding, Provider, UrlProvider, DEFAULT_MAX_URL_LENGTH};
+
+const SAMPLE_QUERY: &str = "what is percent-encoding? (a \"quick\" check & test)";
+
+/// Asks for a custom provider's settings on stdin, tries a sample query, and saves it to the
+/// config file.
+pub fn add_provider(config: &Config, browser: &dyn Browser) -> Result<PathBuf> {
+    let name = ask("Name", None)?;
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!("Provider names may only use letters, digits, '-' and '_'");
+    }
+    if provider::is_builtin(&name) || config.provider(&name).is_some() {
+        anyhow::bail!("A provider named {} already exists", name);
+    }
+
+    let url = ask("URL template, with {query} where the prompt goes", None)?;
+    if !url.contains("{query}") {
+        anyhow::bail!("The URL template needs a {{query}} placeholder");
+    }
+    url::Url::parse(&url.replace("{query}", "test"))
+        .with_context(|| format!("{:?} is not a valid URL", url))?;
+
+    let encoding = ask("Encoding (query, form, or strict)", Some("query"))?;
+    let encoding = Encoding::from_str(&encoding, true).map_err(|e| anyhow::anyhow!(e))?;
+
+    let default_length = DEFAULT_MAX_URL_LENGTH.to_string();
+    let max_url_length: usize = ask("Maximum URL length", Some(&default_length))?
+        .parse()
+        .context("The maximum URL length must be a number")?;
+
+    let custom = ProviderConfig {
+        url: Some(url.clone()),
+        encoding: Some(encoding),
+        max_url_length: Some(max_url_length),
+        ..Default::default()
+    };
+    let preview = Config {
+        providers: HashMap::from([(name.clone(), custom)]),
+        ..Default::default()
+    };
+    let provider = UrlProvider::resolve(&name, &preview)?;
+
+    if confirm("Open a sample query to check it?")? {
+        browser.open(&provider.query_url(SAMPLE_QUERY))?;
+        if !confirm("Did the query arrive intact?")? {
+            anyhow::bail!("Not saved; try another encoding or URL template");
+        }
+    }
+
+    let encoding = encoding
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default();
+    let section = format!(
+        "[providers.{}]\nurl = {}\nencoding = {}\nmax_url_length = {}\n",
+        name,
+        toml::Value::String(url),
+        toml::Value::String(encoding),
+        max_url_length
+    );
+    config::append(&section)
+}
+
+fn ask(question: &str, default: Option<&str>) -> Result<String> {
+    match default {
+        Some(default) => eprint!("{} [{}]: ", question, default),
+        None => eprint!("{}: ", question),
+    }
+    std::io::stderr().flush()?;
+
+    let mut line = String::new();
+    if std::io::stdin().lock().read_line(&mut line)? == 0 {
+        anyhow::bail!("No answer given");
+    }
+    let answer = line.trim();
+    match (answer.is_empty(), default) {
+        (true, Some(default)) => Ok(default.to_string()),
+        (true, None) => anyhow::bail!("{} is required", question),
+        _ => Ok(answer.to_string()),
+    }
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    let answer = ask(&format!("{} (y/n)", question), Some("y"))?;
+    Ok(answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes"))
+}
@@ -0,0 +1,122 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::LazyLock;
+
+use crate::error::SearchError;
+
+static DETECTED: LazyLock<bool> = LazyLock::new(|| {
+    cfg!(target_os = "linux")
+        && (std::env::var_os("WSL_DISTRO_NAME").is_some()
+            || std::fs::read_to_string("/proc/sys/kernel/osrelease")
+                .is_ok_and(|release| release.to_lowercase().contains("microsoft")))
+});
+
+/// Whether this is Linux running under the Windows Subsystem for Linux, where the browser and
+/// clipboard belong to Windows.
+pub fn detected() -> bool {
+    *DETECTED
+}
+
+/// Opens the URL in the Windows default browser.
+pub fn open(url: &str) -> Result<(), SearchError> {
+    if Command::new("wslview")
+        .arg(url)
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+    {
+        return Ok(());
+    }
+    let status = powershell(&format!("Start-Process '{}'", url.replace('\'', "''")))
+        .status()
+        .map_err(|e| {
+            SearchError::BrowserFailed(format!(
+                "Failed to open the browser through wslview or powershell.exe: {}",
+                e
+            ))
+        })?;
+    if !status.success() {
+        return Err(SearchError::BrowserFailed(format!(
+            "powershell.exe Start-Process exited with {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+pub fn get_text() -> Result<String, SearchError> {
+    let output =
+        powershell("[Console]::OutputEncoding = [Text.Encoding]::UTF8; Get-Clipboard -Raw")
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|e| unavailable(&e.to_string()))?;
+    if !output.status.success() {
+        return Err(unavailable("powershell.exe Get-Clipboard failed"));
+    }
+    let text = String::from_utf8_lossy(&output.stdout).replace("\r\n", "\n");
+    Ok(text.strip_suffix('\n').unwrap_or(&text).to_string())
+}
+
+/// Copies through clip.exe, which reads UTF-16 correctly only with a byte order mark.
+pub fn set_text(text: &str) -> Result<(), SearchError> {
+    let mut child = Command::new("clip.exe")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| unavailable(&e.to_string()))?;
+    let mut bytes = vec![0xFF, 0xFE];
+    for unit in text.replace('\n', "\r\n").encode_utf16() {
+        bytes.extend(unit.to_le_bytes());
+    }
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin
+        .write_all(&bytes)
+        .map_err(|e| unavailable(&e.to_string()))?;
+    drop(stdin);
+    match child.wait() {
+        Ok(status) if status.success() => Ok(()),
+        _ => Err(unavailable("clip.exe failed")),
+    }
+}
+
+/// Translates a Windows path like `C:\Users\me\notes.txt` when running under WSL.
+pub fn translate_path(path: &str) -> String {
+    let Some(fallback) = linux_path(path).filter(|_| detected()) else {
+        return path.to_string();
+    };
+    // wslpath knows where drives are mounted when /etc/wsl.conf moves them.
+    Command::new("wslpath")
+        .args(["-u", path])
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|path| !path.is_empty())
+        .unwrap_or(fallback)
+}
+
+/// The path under the default `/mnt` mount for a path with a Windows drive letter.
+pub fn linux_path(path: &str) -> Option<String> {
+    let mut chars = path.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    let rest = chars.as_str().strip_prefix(':')?;
+    if !(rest.is_empty() || rest.starts_with(['\\', '/'])) {
+        return None;
+    }
+    Some(format!(
+        "/mnt/{}{}",
+        drive.to_ascii_lowercase(),
+        rest.replace('\\', "/")
+    ))
+}
+
+fn powershell(script: &str) -> Command {
+    let mut command = Command::new("powershell.exe");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", script]);
+    command
+}
+
+fn unavailable(message: &str) -> SearchError {
+    SearchError::ClipboardUnavailable(message.to_string())
+}
@@ -0,0 +1,303 @@
+use search::clipboard::MemoryClipboard;
+use search::config::Config;
+use search::format::{Order, Paste};
+use search::provider::Style;
+use search::source::{self, ContextSource};
+use search::{compose, ComposeOptions, SearchError};
+
+fn options(provider: &str, prompt: &str) -> ComposeOptions {
+    ComposeOptions {
+        provider: provider.to_string(),
+        prompt: prompt.to_string(),
+        ..Default::default()
+    }
+}
+
+fn fixture() -> Box<dyn ContextSource> {
+    Box::new(source::Paths {
+        paths: vec!["tests/fixtures/project".to_string()],
+        max_file_bytes: 100_000,
+    })
+}
+
+#[test]
+fn prompt_without_context_is_unchanged() {
+    let composed = compose(
+        &options("chatgpt", "what is an inframodel"),
+        &[],
+        &Config::default(),
+    )
+    .unwrap();
+    assert_eq!(composed.query, "what is an inframodel");
+    assert_eq!(composed.provider.name, "chatgpt");
+    assert!(composed.sources.is_empty());
+}
+
+#[test]
+fn files_use_the_provider_style() {
+    let config = Config::default();
+    let markdown = compose(&options("chatgpt", "explain"), &[fixture()], &config).unwrap();
+    assert!(markdown.query.contains("```python\ndef greet(name):"));
+    assert!(markdown.query.ends_with("\nexplain"));
+
+    let xml = compose(&options("claude", "explain"), &[fixture()], &config).unwrap();
+    assert!(xml.query.contains("greet.py"));
+    assert!(xml.query.contains('<'));
+    assert!(!xml.query.contains("```"));
+}
+
+#[test]
+fn style_override_wins_over_the_provider_default() {
+    let mut options = options("claude", "explain");
+    options.style = Some(Style::Markdown);
+    let composed = compose(&options, &[fixture()], &Config::default()).unwrap();
+    assert!(composed.query.contains("```python"));
+}
+
+#[test]
+fn order_controls_prompt_and_section_placement() {
+    let sources: Vec<Box<dyn ContextSource>> = vec![
+        Box::new(source::Clipboard(Box::new(MemoryClipboard::new(
+            "Traceback: boom",
+        )))),
+        fixture(),
+    ];
+    let mut options = options("chatgpt", "why");
+    options.order = Some(Order::PromptFirst);
+    options.source_order = vec!["files".to_string(), "clipboard".to_string()];
+    let composed = compose(&options, &sources, &Config::default()).unwrap();
+
+    assert!(composed.query.starts_with("why\n"));
+    let files = composed.query.find("def greet").unwrap();
+    let clipboard = composed.query.find("Traceback: boom").unwrap();
+    assert!(files < clipboard);
+}
+
+#[test]
+fn sources_list_pastes_then_files() {
+    let sources: Vec<Box<dyn ContextSource>> = vec![
+        Box::new(source::Clipboard(Box::new(MemoryClipboard::new(
+            "Traceback: boom",
+        )))),
+        fixture(),
+    ];
+    let composed = compose(&options("chatgpt", "why"), &sources, &Config::default()).unwrap();
+    assert!(composed.query.contains("Traceback: boom"));
+    let files: Vec<&str> = composed.sources.iter().map(String::as_str).collect();
+    assert_eq!(
+        files,
+        [
+            "clipboard",
+            "tests/fixtures/project/README.md",
+            "tests/fixtures/project/greet.py"
+        ]
+    );
+}
+
+#[test]
+fn files_reached_twice_are_included_once() {
+    let sources: Vec<Box<dyn ContextSource>> = vec![
+        fixture(),
+        Box::new(source::Paths {
+            paths: vec!["tests/fixtures/project/greet.py".to_string()],
+            max_file_bytes: 100_000,
+        }),
+    ];
+    let composed = compose(&options("chatgpt", "why"), &sources, &Config::default()).unwrap();
+    assert_eq!(composed.sources.len(), 2);
+    assert_eq!(composed.query.matches("def greet").count(), 1);
+}
+
+#[test]
+fn empty_pastes_are_not_sources() {
+    let sources: Vec<Box<dyn ContextSource>> = vec![Box::new(Paste {
+        source: "selection",
+        content: String::new(),
+    })];
+    let composed = compose(&options("chatgpt", "hi"), &sources, &Config::default()).unwrap();
+    assert!(composed.sources.is_empty());
+    assert_eq!(composed.query, "hi");
+}
+
+#[test]
+fn budget_omits_trailing_files() {
+    let mut options = options("chatgpt", "explain");
+    options.budget = Some(35);
+    let composed = compose(&options, &[fixture()], &Config::default()).unwrap();
+    assert!(composed.query.contains("A small fixture."));
+    assert!(!composed.query.contains("def greet"));
+}
+
+#[test]
+fn budget_keeps_the_files_most_relevant_to_the_prompt() {
+    let mut options = options("chatgpt", "how does greet build its message");
+    options.budget = Some(60);
+    let composed = compose(&options, &[fixture()], &Config::default()).unwrap();
+    assert!(composed.query.contains("def greet"));
+    assert!(!composed.query.contains("A small fixture."));
+}
+
+#[test]
+fn secrets_are_redacted_and_flagged() {
+    let key = "sk-proj-abcdefghijklmnopqrstuvwxyz0123456789";
+    let composed = compose(
+        &options("chatgpt", &format!("why does {} fail", key)),
+        &[],
+        &Config::default(),
+    )
+    .unwrap();
+    assert!(composed.redacted);
+    assert!(!composed.query.contains(key));
+    let change = &composed.redactions[0];
+    assert!(change.before.contains(key));
+    assert_eq!(change.after, "why does [REDACTED API key] fail");
+
+    let mut raw = options("chatgpt", &format!("why does {} fail", key));
+    raw.no_redact = true;
+    let composed = compose(&raw, &[], &Config::default()).unwrap();
+    assert!(!composed.redacted);
+    assert!(composed.query.contains(key));
+}
+
+#[test]
+fn configured_rules_and_allowlist_are_honored() {
+    let mut config = Config::default();
+    config
+        .redact
+        .rules
+        .insert("ticket".into(), r"\bACME-\d{6}\b".into());
+    config
+        .redact
+        .allow
+        .insert("fixture".into(), r"^sk-test-".into());
+
+    let fixture_key = "sk-test-abcdefghijklmnopqrstuvwxyz";
+    let prompt = format!("why does {} fail for ACME-123456", fixture_key);
+    let composed = compose(&options("chatgpt", &prompt), &[], &config).unwrap();
+    assert!(composed.query.contains(fixture_key));
+    assert!(composed.query.contains("[REDACTED ticket]"));
+}
+
+#[test]
+fn escape_codes_are_stripped_unless_raw() {
+    let sources: Vec<Box<dyn ContextSource>> = vec![Box::new(Paste {
+        source: "terminal",
+        content: "\x1b[31merror\x1b[0m".to_string(),
+    })];
+    let composed = compose(&options("chatgpt", "fix"), &sources, &Config::default()).unwrap();
+    assert!(composed.query.contains("error"));
+    assert!(!composed.query.contains('\x1b'));
+
+    let mut raw = options("chatgpt", "fix");
+    raw.raw = true;
+    let composed = compose(&raw, &sources, &Config::default()).unwrap();
+    assert!(composed.query.contains("\x1b[31m"));
+}
+
+#[test]
+fn unknown_provider_is_a_typed_error() {
+    let error = compose(&options("nope", "hi"), &[], &Config::default())
+        .err()
+        .unwrap();
+    assert!(matches!(error, SearchError::UnknownProvider(name) if name == "nope"));
+}
+
+#[test]
+fn empty_context_directory_is_a_typed_error() {
+    let dir = std::env::temp_dir().join(format!("search-empty-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let sources: Vec<Box<dyn ContextSource>> = vec![Box::new(source::Paths {
+        paths: vec![dir.display().to_string()],
+        max_file_bytes: 100_000,
+    })];
+    let error = compose(&options("chatgpt", "hi"), &sources, &Config::default())
+        .err()
+        .unwrap();
+    std::fs::remove_dir(&dir).unwrap();
+    assert!(matches!(error, SearchError::NoContext));
+}
+
+#[test]
+fn lang_adds_an_instruction_or_a_locale_parameter() {
+    let mut chat = options("chatgpt", "how does this work");
+    chat.lang = Some("de".to_string());
+    let composed = compose(&chat, &[], &Config::default()).unwrap();
+    assert_eq!(composed.query, "how does this work\n\nAnswer in German.");
+
+    let mut search = options("google", "rust traits");
+    search.lang = Some("German".to_string());
+    let composed = compose(&search, &[], &Config::default()).unwrap();
+    assert_eq!(composed.query, "rust traits");
+    assert!(search::Provider::query_url(&composed.provider, &composed.query).ends_with("&hl=de"));
+}
+
+#[test]
+fn locale_maps_to_each_search_engine() {
+    let url = |provider: &str| {
+        let mut options = options(provider, "rust");
+        options.locale = Some("de-DE".to_string());
+        let composed = compose(&options, &[], &Config::default()).unwrap();
+        search::Provider::query_url(&composed.provider, &composed.query)
+    };
+    assert_eq!(
+        url("google"),
+        "https://www.google.com/search?q=rust&hl=de&gl=de"
+    );
+    assert_eq!(url("duckduckgo"), "https://duckduckgo.com/?q=rust&kl=de-de");
+    assert_eq!(url("chatgpt"), "https://chatgpt.com/?q=rust");
+}
+
+#[cfg(feature = "clipboard")]
+#[test]
+fn copied_html_is_converted_to_markdown() {
+    let clipboard = MemoryClipboard::with_html(
+        "See the docs",
+        "<p>See the <a href=\"https://docs.rs\">docs</a></p><pre><code>cargo doc</code></pre>",
+    );
+    let sources: Vec<Box<dyn ContextSource>> =
+        vec![Box::new(source::Clipboard(Box::new(clipboard)))];
+    let composed = compose(
+        &options("chatgpt", "summarize"),
+        &sources,
+        &Config::default(),
+    )
+    .unwrap();
+    assert!(composed.query.contains("[docs](https://docs.rs)"));
+    assert!(composed.query.contains("cargo doc"));
+
+    let styled = MemoryClipboard::with_html("let x = 1;", "<div><span>let x = 1;</span></div>");
+    let sources: Vec<Box<dyn ContextSource>> = vec![Box::new(source::Clipboard(Box::new(styled)))];
+    let composed = compose(&options("chatgpt", "explain"), &sources, &Config::default()).unwrap();
+    assert!(composed.query.contains("let x = 1;"));
+    assert!(!composed.query.contains("<span>"));
+}
+
+#[test]
+fn empty_clipboard_sends_just_the_prompt() {
+    let sources: Vec<Box<dyn ContextSource>> = vec![Box::new(source::Clipboard(Box::new(
+        MemoryClipboard::default(),
+    )))];
+    let composed = compose(&options("chatgpt", "hello"), &sources, &Config::default()).unwrap();
+    assert_eq!(composed.query, "hello");
+    assert!(composed.sources.is_empty());
+}
+
+#[test]
+fn repository_header_comes_first_in_xml() {
+    let sources: Vec<Box<dyn ContextSource>> = vec![
+        Box::new(source::Clipboard(Box::new(MemoryClipboard::new(
+            "Traceback: boom",
+        )))),
+        fixture(),
+    ];
+    let mut options = options("claude", "why");
+    options.repo_header = true;
+    let composed = compose(&options, &sources, &Config::default()).unwrap();
+    let repo = composed.query.find("Repository: ").unwrap();
+    assert!(repo < composed.query.find("<documents>").unwrap());
+
+    options.source_order = vec!["files".to_string(), "clipboard".to_string()];
+    let composed = compose(&options, &sources, &Config::default()).unwrap();
+    let repo = composed.query.find("Repository: ").unwrap();
+    assert!(repo < composed.query.find("<documents>").unwrap());
+}
@@ -0,0 +1,20 @@
+use search::config::ConfirmConfig;
+
+#[test]
+fn prompts_over_either_limit_need_confirming() {
+    assert!(!ConfirmConfig::default().exceeded(usize::MAX));
+
+    let limits = ConfirmConfig {
+        bytes: Some(1000),
+        tokens: Some(100),
+    };
+    assert!(!limits.exceeded(400));
+    assert!(limits.exceeded(401));
+
+    let limits = ConfirmConfig {
+        bytes: Some(1000),
+        tokens: None,
+    };
+    assert!(!limits.exceeded(1000));
+    assert!(limits.exceeded(1001));
+}
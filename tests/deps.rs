@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+
+use search::deps::local_dependencies;
+
+fn tree(name: &str, files: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("search-deps-{}-{}", std::process::id(), name));
+    for file in files {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+    dir
+}
+
+/// Paths under `dir`, compared by component so `web/./lib` matches `web/lib`.
+fn relative(dir: &Path, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .map(|path| path.strip_prefix(dir).unwrap().to_path_buf())
+        .collect()
+}
+
+fn paths(names: &[&str]) -> Vec<PathBuf> {
+    names.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn rust_modules_and_use_trees_resolve_to_files() {
+    let dir = tree(
+        "rust",
+        &[
+            "Cargo.toml",
+            "src/main.rs",
+            "src/cli.rs",
+            "src/net/mod.rs",
+            "src/net/client.rs",
+        ],
+    );
+    let content = "mod cli;\n// mod commented;\nuse crate::net::{client::Client, Server};\nuse std::io;\nuse serde::Deserialize;\n";
+    let found = local_dependencies(&dir.join("src/main.rs"), content);
+    let found = relative(&dir, found);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        found,
+        paths(&["src/cli.rs", "src/net/client.rs", "src/net/mod.rs"])
+    );
+}
+
+#[test]
+fn python_and_js_imports_resolve_relative_to_the_file() {
+    let dir = tree(
+        "pyjs",
+        &[
+            "app/main.py",
+            "app/util.py",
+            "app/models/__init__.py",
+            "web/index.ts",
+            "web/lib/api.ts",
+        ],
+    );
+    let python = "import os\nfrom . import (\n    util,\n)\nfrom .models import User\n";
+    let found = relative(&dir, local_dependencies(&dir.join("app/main.py"), python));
+    assert_eq!(found, paths(&["app/util.py", "app/models/__init__.py"]));
+
+    let js = "import { get } from './lib/api';\nimport React from 'react';\n";
+    let found = relative(&dir, local_dependencies(&dir.join("web/index.ts"), js));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(found, paths(&["web/lib/api.ts"]));
+}
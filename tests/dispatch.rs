@@ -0,0 +1,97 @@
+use search::browser::{BrowserOptions, RecordingBrowser};
+use search::clipboard::MemoryClipboard;
+use search::config::Config;
+use search::dispatch::UrlDispatcher;
+use search::provider::{Provider, UrlProvider};
+
+fn dispatcher(open: bool) -> UrlDispatcher {
+    UrlDispatcher {
+        open,
+        print_url: false,
+        copy_url: false,
+        qr: false,
+        browser: BrowserOptions::default(),
+    }
+}
+
+fn provider(max_url_length: usize) -> UrlProvider {
+    let mut provider = UrlProvider::resolve("claude", &Config::default()).unwrap();
+    provider.max_url_length = max_url_length;
+    provider
+}
+
+#[test]
+fn short_queries_open_the_query_url() {
+    let browser = RecordingBrowser::default();
+    let clipboard = MemoryClipboard::default();
+    let provider = provider(14_000);
+
+    let url = dispatcher(true)
+        .dispatch_with(&provider, "hello world", &browser, &clipboard)
+        .unwrap();
+    assert_eq!(url, "https://claude.ai/new?q=hello+world");
+    assert_eq!(browser.opened(), [url]);
+    assert_eq!(clipboard.text(), None);
+}
+
+#[test]
+fn long_queries_fall_back_to_the_clipboard() {
+    let browser = RecordingBrowser::default();
+    let clipboard = MemoryClipboard::default();
+    let provider = provider(40);
+    let query = "a prompt that is much too long to fit in the URL";
+
+    let url = dispatcher(true)
+        .dispatch_with(&provider, query, &browser, &clipboard)
+        .unwrap();
+    assert_eq!(url, provider.query_url(query));
+    assert_eq!(browser.opened(), [provider.home()]);
+    assert_eq!(clipboard.text().as_deref(), Some(query));
+}
+
+#[test]
+fn no_open_leaves_the_browser_alone() {
+    let browser = RecordingBrowser::default();
+    let clipboard = MemoryClipboard::default();
+    let provider = provider(40);
+
+    let url = dispatcher(false)
+        .dispatch_with(
+            &provider,
+            "a much longer prompt than forty",
+            &browser,
+            &clipboard,
+        )
+        .unwrap();
+    assert!(url.starts_with("https://claude.ai/new?q="));
+    assert!(browser.opened().is_empty());
+    assert_eq!(clipboard.text(), None);
+}
+
+#[test]
+fn copy_url_puts_the_url_on_the_clipboard() {
+    let browser = RecordingBrowser::default();
+    let clipboard = MemoryClipboard::new("previous");
+    let provider = provider(14_000);
+    let mut dispatcher = dispatcher(false);
+    dispatcher.copy_url = true;
+
+    let url = dispatcher
+        .dispatch_with(&provider, "hi", &browser, &clipboard)
+        .unwrap();
+    assert_eq!(clipboard.text(), Some(url));
+}
+
+#[test]
+fn chats_open_the_thread_and_copy_the_prompt() {
+    let browser = RecordingBrowser::default();
+    let clipboard = MemoryClipboard::default();
+    let mut provider = provider(14_000);
+    provider.open_chat("0a1b-2c3d").unwrap();
+
+    dispatcher(true)
+        .dispatch_with(&provider, "and now?", &browser, &clipboard)
+        .unwrap();
+    assert_eq!(browser.opened(), ["https://claude.ai/chat/0a1b-2c3d"]);
+    assert_eq!(clipboard.text().as_deref(), Some("and now?"));
+}
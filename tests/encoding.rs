@@ -0,0 +1,55 @@
+use search::config::Config;
+use search::provider::{Encoding, Provider, UrlProvider};
+
+#[test]
+fn query_encoding_uses_plus_for_spaces() {
+    let provider = UrlProvider::resolve("chatgpt", &Config::default()).unwrap();
+    assert_eq!(
+        provider.query_url("a b&c=d\n#e"),
+        "https://chatgpt.com/?q=a+b%26c%3Dd%0A%23e"
+    );
+}
+
+#[test]
+fn plus_signs_are_escaped() {
+    assert_eq!(Encoding::Query.encode("1+1", ""), "1%2B1");
+    assert_eq!(Encoding::Form.encode("1+1", ""), "1%2B1");
+}
+
+#[test]
+fn strict_encoding_escapes_everything_but_alphanumerics() {
+    assert_eq!(Encoding::Strict.encode("a b-c.d", ""), "a%20b%2Dc%2Ed");
+    assert_eq!(Encoding::Form.encode("a b-c.d(e)", ""), "a+b-c.d%28e%29");
+}
+
+#[test]
+fn extra_characters_are_escaped() {
+    assert_eq!(Encoding::Query.encode("a,b(c)", ",()"), "a%2Cb%28c%29");
+    assert_eq!(Encoding::Query.encode("1+2", "2"), "1%2B%32");
+}
+
+#[test]
+fn unicode_is_percent_encoded() {
+    assert_eq!(Encoding::Query.encode("café", ""), "caf%C3%A9");
+}
+
+#[test]
+fn custom_providers_come_from_config() {
+    let config: Config = toml::from_str(
+        r#"
+        max_url_length = 5000
+
+        [providers.kagi]
+        url = "https://kagi.com/search?q={query}"
+        encoding = "strict"
+        "#,
+    )
+    .unwrap();
+    let provider = UrlProvider::resolve("kagi", &config).unwrap();
+    assert_eq!(provider.home(), "https://kagi.com/");
+    assert_eq!(provider.max_url_length(), 5000);
+    assert_eq!(provider.query_url("a b"), "https://kagi.com/search?q=a%20b");
+
+    let claude = UrlProvider::resolve("claude", &config).unwrap();
+    assert_eq!(claude.max_url_length(), 5000);
+}
@@ -0,0 +1,10 @@
+use search::format::fence_for;
+
+#[test]
+fn fences_are_longer_than_any_backtick_run_inside() {
+    assert_eq!(fence_for("plain text"), "```");
+    assert_eq!(fence_for("inline `code` here"), "```");
+    assert_eq!(fence_for("```rust\nfn main() {}\n```"), "````");
+    assert_eq!(fence_for("a ````` b ``` c"), "``````");
+    assert_eq!(fence_for("ends with ``"), "```");
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use search::config::HistoryConfig;
+use search::{history, import};
+
+#[test]
+fn chatgpt_export_follows_the_current_branch() {
+    let imported = import::read(Path::new("tests/fixtures/export/chatgpt.json")).unwrap();
+    assert_eq!(imported.conversations, 1);
+
+    let prompts: Vec<&str> = imported.entries.iter().map(|e| e.prompt.as_str()).collect();
+    assert_eq!(prompts, ["What does 'a mean?", "And 'static?"]);
+    let first = &imported.entries[0];
+    assert_eq!(first.provider, "chatgpt");
+    assert_eq!(first.url, "https://chatgpt.com/c/c0ffee");
+    assert_eq!(first.session.as_deref(), Some("Rust lifetimes"));
+    assert_eq!(first.answer.as_deref(), Some("It names a lifetime."));
+}
+
+#[test]
+fn claude_export_reads_text_from_content_parts() {
+    let imported = import::read(Path::new("tests/fixtures/export/claude.json")).unwrap();
+    let entry = &imported.entries[0];
+    assert_eq!(entry.provider, "claude");
+    assert_eq!(entry.prompt, "Match a date");
+    assert_eq!(entry.answer.as_deref(), Some(r"Try \d{4}-\d{2}-\d{2}"));
+    assert_eq!(entry.timestamp.to_rfc3339(), "2026-01-02T10:00:01+00:00");
+}
+
+#[test]
+fn imports_are_not_the_last_or_a_recent_query() {
+    let dir = std::env::temp_dir().join(format!("search-import-{}", std::process::id()));
+    std::env::set_var("SEARCH_DATA_DIR", &dir);
+    let config = HistoryConfig::default();
+    let sent = history::Entry::new(
+        "google",
+        "rust lifetimes",
+        "rust lifetimes",
+        "https://g/?q=r",
+    );
+    history::record(&config, sent).unwrap();
+    let imported = import::read(Path::new("tests/fixtures/export/chatgpt.json")).unwrap();
+    history::record_all(&config, imported.entries).unwrap();
+
+    let last = history::last(&config, None).unwrap().unwrap();
+    assert_eq!(last.prompt, "rust lifetimes");
+    let recent = history::find_recent(&config, "rust lifetimes", 1).unwrap();
+    assert!(recent.is_some());
+    assert!(history::last(&config, Some("Rust lifetimes"))
+        .unwrap()
+        .is_none());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn chatgpt_export_with_a_parent_cycle_is_rejected() {
+    let path = std::env::temp_dir().join(format!("search-cycle-{}.json", std::process::id()));
+    let export = r#"[{"id": "c", "title": "Loop", "current_node": "a", "mapping": {
+        "a": {"parent": "b", "message": null},
+        "b": {"parent": "a", "message": null}
+    }}]"#;
+    std::fs::write(&path, export).unwrap();
+    let result = import::read(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(format!("{:#}", result.err().unwrap()).contains("its own ancestor"));
+}
@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use search::manifest;
+
+fn project(files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "search-manifest-{}-{}",
+        std::process::id(),
+        files[0].0
+    ));
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    for (name, content) in files {
+        std::fs::write(dir.join(name), content).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn cargo_lockfiles_resolve_only_direct_dependencies() {
+    let dir = project(&[
+        (
+            "Cargo.toml",
+            "[package]\nname = \"app\"\n\n[dependencies]\nserde = \"1\"\n\n[dev-dependencies]\ntempfile = \"3\"\n",
+        ),
+        (
+            "Cargo.lock",
+            "version = 3\n\n[[package]]\nname = \"serde\"\nversion = \"1.0.200\"\n\n[[package]]\nname = \"tempfile\"\nversion = \"3.10.1\"\n\n[[package]]\nname = \"itoa\"\nversion = \"1.0.11\"\n",
+        ),
+    ]);
+    // The project is found from a subdirectory too.
+    let files = manifest::collect_in(&dir.join("src"), 100_000).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(files.len(), 2);
+    assert!(files[0].content.contains("serde = \"1\""));
+    assert_eq!(
+        files[1].content,
+        "Resolved direct dependencies:\nserde 1.0.200\ntempfile 3.10.1"
+    );
+    assert_eq!(
+        files[1].note.as_deref(),
+        Some("summary of 3 locked packages")
+    );
+}
+
+#[test]
+fn npm_and_python_names_are_matched_as_their_lockfiles_write_them() {
+    let dir = project(&[
+        (
+            "package.json",
+            r#"{"dependencies": {"left-pad": "^1.3.0"}}"#,
+        ),
+        (
+            "package-lock.json",
+            r#"{"packages": {
+                "": {"name": "app"},
+                "node_modules/left-pad": {"version": "1.3.0"},
+                "node_modules/a/node_modules/left-pad": {"version": "0.1.0"}
+            }}"#,
+        ),
+        (
+            "pyproject.toml",
+            "[project]\ndependencies = [\"Requests_OAuthlib>=1.0\", \"ruamel.yaml\"]\n",
+        ),
+        (
+            "uv.lock",
+            "[[package]]\nname = \"requests-oauthlib\"\nversion = \"2.0.0\"\n\n[[package]]\nname = \"ruamel-yaml\"\nversion = \"0.18.6\"\n",
+        ),
+    ]);
+    let files = manifest::collect_in(&dir, 100_000).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(files.len(), 4);
+    assert_eq!(
+        files[1].content,
+        "Resolved direct dependencies:\nleft-pad 1.3.0"
+    );
+    assert_eq!(
+        files[3].content,
+        "Resolved direct dependencies:\nrequests-oauthlib 2.0.0\nruamel-yaml 0.18.6"
+    );
+}
+
+#[test]
+fn malformed_lockfiles_are_an_error() {
+    let dir = project(&[
+        ("Cargo.toml", "[dependencies]\nserde = \"1\"\n"),
+        ("Cargo.lock", "[[package]\n"),
+    ]);
+    let result = manifest::collect_in(&dir, 100_000);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(format!("{:#}", result.err().unwrap()).contains("Failed to parse"));
+}
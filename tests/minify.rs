@@ -0,0 +1,31 @@
+use search::minify::minify;
+
+#[test]
+fn license_headers_banners_and_blank_runs_are_dropped_from_code() {
+    let source = "// Copyright 2024 Example Corp\n// SPDX-License-Identifier: MIT\n\nuse std::io;\n\n\n\n// ==========\n// Helpers\n// ----------\nfn main() {}\n";
+    assert_eq!(
+        minify(source, Some("rust")),
+        "use std::io;\n\n// Helpers\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn ordinary_comments_are_kept() {
+    let source = "#!/usr/bin/env python3\n# Greets people.\nprint('hi')\n";
+    assert_eq!(minify(source, Some("python")), source);
+}
+
+#[test]
+fn banner_like_lines_are_content_in_markdown_yaml_and_strings() {
+    let markdown = "Title\n--------\n\n| a | b |\n|---|---|\n";
+    assert_eq!(minify(markdown, Some("markdown")), markdown);
+
+    let yaml = "a: 1\n------------\nb: 2\n";
+    assert_eq!(minify(yaml, Some("yaml")), yaml);
+
+    let rust = "let rule = \"\n==========\n\";\n";
+    assert_eq!(minify(rust, Some("rust")), rust);
+
+    let text = "Notes\n========\n";
+    assert_eq!(minify(text, None), text);
+}
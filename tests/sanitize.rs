@@ -0,0 +1,23 @@
+use search::sanitize::sanitize;
+
+#[test]
+fn terminal_escapes_and_overwrites_are_resolved() {
+    assert_eq!(sanitize("\x1b[1;31merror\x1b[0m: boom"), "error: boom");
+    assert_eq!(sanitize("\x1b]8;;https://x\x1b\\link\x1b]8;;\x07"), "link");
+    assert_eq!(sanitize("10%\r50%\r100%\ndone\r\n"), "100%\ndone\n");
+    assert_eq!(sanitize("tpyo\x08\x08\x08ypo"), "typo");
+    assert_eq!(sanitize("a\x00b\tc"), "ab\tc");
+}
+
+#[test]
+fn zero_width_characters_are_dropped_but_joiners_kept() {
+    assert_eq!(sanitize("pass\u{200b}word\u{feff}"), "password");
+    // Persian "mi-khaham" needs its non-joiner, and emoji sequences need the joiner.
+    assert_eq!(sanitize("می\u{200c}خواهم"), "می\u{200c}خواهم");
+    assert_eq!(sanitize("👩\u{200d}💻"), "👩\u{200d}💻");
+}
+
+#[test]
+fn text_is_nfc_normalized() {
+    assert_eq!(sanitize("cafe\u{301}"), "caf\u{e9}");
+}
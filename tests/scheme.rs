@@ -0,0 +1,65 @@
+use serde_json::json;
+
+use search::scheme::{self, confine_context};
+
+#[test]
+fn links_become_tool_arguments() {
+    let request = scheme::parse(
+        "search://query?q=why+%3F&p=claude&x=src/main.rs&x=Cargo.toml&minify=1&line_numbers=false&budget=4000&x-success=app://done",
+    )
+    .unwrap();
+    assert_eq!(
+        request.arguments,
+        json!({
+            "prompt": "why ?",
+            "provider": "claude",
+            "context": ["src/main.rs", "Cargo.toml"],
+            "minify": true,
+            "line_numbers": false,
+            "budget": 4000,
+        })
+    );
+    assert_eq!(request.success.as_deref(), Some("app://done"));
+    assert_eq!(request.error, None);
+}
+
+#[test]
+fn other_schemes_actions_and_budgets_are_rejected() {
+    assert!(scheme::parse("https://query?q=hi").is_err());
+    assert!(scheme::parse("search://delete?q=hi").is_err());
+    assert!(scheme::parse("search://query?budget=lots").is_err());
+    assert!(scheme::parse("search:?q=hi").is_ok());
+}
+
+#[test]
+fn link_context_stays_under_the_directory() {
+    let root = std::env::temp_dir().join(format!("search-scheme-{}", std::process::id()));
+    let dir = root.join("project");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+    std::fs::write(root.join("secret"), "key").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(root.join("secret"), dir.join("link")).unwrap();
+
+    let confine = |query: &str| {
+        let mut request = scheme::parse(&format!("search://query?q=hi&{}", query)).unwrap();
+        confine_context(&mut request, &dir).map(|paths| (paths, request.arguments))
+    };
+    let (paths, arguments) = confine("x=src/main.rs").unwrap();
+    let main = dir.canonicalize().unwrap().join("src/main.rs");
+    assert_eq!(arguments["context"], json!([main.display().to_string()]));
+    assert_eq!(paths, [main]);
+
+    assert!(confine("x=../secret").is_err());
+    assert!(confine("x=src/../../secret").is_err());
+    assert!(confine(&format!("x={}", root.join("secret").display())).is_err());
+    assert!(confine("x=~/.ssh/config").is_err());
+    #[cfg(unix)]
+    assert!(confine("x=link").is_err());
+    // The manifest is found in a parent directory, so it's outside too.
+    std::fs::write(root.join("Cargo.toml"), "[package]").unwrap();
+    assert!(confine("manifest=1").is_err());
+    assert!(confine("q=only").unwrap().0.is_empty());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,10 @@
+use search::snip::code_blocks;
+
+#[test]
+fn code_blocks_are_found_between_matching_fences() {
+    let reply = "Run this:\n\n```bash\ncargo clean\ncargo build\n```\n\nor, with a nested fence:\n\n````md\n```\ninner\n```\n````\n";
+    assert_eq!(
+        code_blocks(reply),
+        ["cargo clean\ncargo build", "```\ninner\n```"]
+    );
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use search::tail::{last_lines, parse_duration};
+
+#[test]
+fn durations_take_a_unit_suffix() {
+    assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+    assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+    assert_eq!(parse_duration(" 2h "), Ok(Duration::from_secs(7200)));
+    assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(86_400)));
+    assert_eq!(parse_duration("0s"), Ok(Duration::ZERO));
+}
+
+#[test]
+fn bad_and_overflowing_durations_are_rejected() {
+    assert!(parse_duration("").is_err());
+    assert!(parse_duration("m").is_err());
+    assert!(parse_duration("5w")
+        .unwrap_err()
+        .contains("unknown duration unit"));
+    assert!(parse_duration("-5m").is_err());
+    assert!(parse_duration("99999999999999999999").is_err());
+    assert!(parse_duration("999999999999999999d")
+        .unwrap_err()
+        .contains("too large"));
+}
+
+#[test]
+fn last_lines_counts_from_the_end() {
+    let text = "one\ntwo\nthree\n";
+    assert_eq!(last_lines(text, 2), "two\nthree");
+    assert_eq!(last_lines(text, 1), "three");
+    assert_eq!(last_lines(text, 10), "one\ntwo\nthree");
+    assert_eq!(last_lines(text, 0), "");
+    assert_eq!(last_lines("", 3), "");
+}
@@ -0,0 +1,12 @@
+use search::wsl::linux_path;
+
+#[test]
+fn windows_drive_paths_map_to_mnt() {
+    assert_eq!(
+        linux_path(r"C:\Users\me\notes.txt").as_deref(),
+        Some("/mnt/c/Users/me/notes.txt")
+    );
+    assert_eq!(linux_path("D:/src").as_deref(), Some("/mnt/d/src"));
+    assert_eq!(linux_path("src/main.rs"), None);
+    assert_eq!(linux_path("a:b"), None);
+}